@@ -1,10 +1,142 @@
-use criterion::{Criterion, criterion_group, criterion_main};
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 
-use xxh::xxh64_slice;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use xxh::{Xxh64, Xxh64BuildHasher, Xxh64Config, xxh64_backend_name, xxh64_slice};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("xxh64", |b| b.iter(|| xxh64_slice(b"1111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111", 0)));
 }
 
-criterion_group!(benches, criterion_benchmark);
-criterion_main!(benches);
\ No newline at end of file
+// One-shot and streaming throughput across input sizes.
+// Compare `cargo bench` against `cargo bench --features unstable-simd` to measure the SIMD stripe loop.
+pub fn throughput_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xxh64_throughput");
+    for &size in [8, 64, 1024, 16 * 1024, 1024 * 1024].iter() {
+        let data = vec![1u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("oneshot", size), &data, |b, data| {
+            b.iter(|| xxh64_slice(black_box(data), 0))
+        });
+        group.bench_with_input(BenchmarkId::new("streaming", size), &data, |b, data| {
+            b.iter(|| {
+                let mut digest = Xxh64::with_seed(0);
+                digest.write(black_box(data));
+                digest.finish()
+            })
+        });
+    }
+    group.finish();
+}
+
+// Inputs shorter than a stripe, as used for HashMap keys.
+pub fn small_input_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xxh64_small");
+    for &size in [0, 1, 4, 8, 12, 16, 31].iter() {
+        let data = vec![1u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("oneshot", size), &data, |b, data| {
+            b.iter(|| xxh64_slice(black_box(data), 0))
+        });
+    }
+    group.finish();
+}
+
+// A 1 GiB input, far larger than the cache.
+// Compare `cargo bench` against `cargo bench --features prefetch` to measure prefetching.
+// The input is built on first use, so runs that filter this benchmark out skip the allocation.
+pub fn large_input_benchmark(c: &mut Criterion) {
+    const SIZE: usize = 1 << 30;
+    let mut data: Option<Vec<u8>> = None;
+    let mut group = c.benchmark_group("xxh64_large");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(SIZE as u64));
+    group.bench_function("oneshot_1GiB", |b| {
+        let data = data.get_or_insert_with(|| (0..SIZE).map(|i| (i * 31 + 7) as u8).collect());
+        b.iter(|| xxh64_slice(black_box(data), 0))
+    });
+    group.finish();
+}
+
+// The stripe loop of the selected backend, named in the group so that runs with and
+// without `--features unstable-simd` (AVX2 on x86_64, NEON on aarch64) can be told apart.
+pub fn backend_benchmark(c: &mut Criterion) {
+    let data: Vec<u8> = (0..1usize << 20).map(|i| (i * 31 + 7) as u8).collect();
+    let mut group = c.benchmark_group(format!("xxh64_backend_{}", xxh64_backend_name()));
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("oneshot_1MiB", |b| b.iter(|| xxh64_slice(black_box(&data), 0)));
+    group.finish();
+}
+
+// `finish` with and without the final avalanche, on 8-byte keys where it is a large share
+// of the work.
+pub fn no_avalanche_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xxh64_no_avalanche");
+    for &avalanche in [true, false].iter() {
+        let config = Xxh64Config { seed: 0, avalanche };
+        group.bench_with_input(BenchmarkId::new("u64", avalanche), &config, |b, config| {
+            b.iter(|| {
+                let mut digest = Xxh64::with_config(*config);
+                digest.write_u64(black_box(0x0123_4567_89ab_cdef));
+                digest.finish()
+            })
+        });
+    }
+    group.finish();
+}
+
+// Lookups in a HashMap with u64 keys, dominated by hashing 8-byte inputs.
+pub fn hashmap_benchmark(c: &mut Criterion) {
+    let map: HashMap<u64, u64, Xxh64BuildHasher> = (0..1000).map(|i| (i, i)).collect();
+    c.bench_function("xxh64_hashmap_u64_lookup", |b| {
+        b.iter(|| (0..1000).filter(|key| map.contains_key(black_box(key))).count())
+    });
+}
+
+#[derive(Hash)]
+struct Record {
+    kind: u8,
+    flags: u8,
+    id: u32,
+    count: u32,
+    tag: u8,
+    parts: [u32; 4],
+}
+
+// Hashing a struct through `#[derive(Hash)]`, which makes many small `write_u8` and
+// `write_u32` calls.
+pub fn derive_hash_benchmark(c: &mut Criterion) {
+    let records: Vec<Record> = (0..1000u32)
+        .map(|i| Record {
+            kind: i as u8,
+            flags: 3,
+            id: i,
+            count: i * 7,
+            tag: 1,
+            parts: [i, 1, 2, 3],
+        })
+        .collect();
+    c.bench_function("xxh64_derive_hash", |b| {
+        b.iter(|| {
+            black_box(&records).iter().fold(0, |acc, record| {
+                let mut hasher = Xxh64::with_seed(0);
+                record.hash(&mut hasher);
+                acc ^ hasher.finish()
+            })
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    throughput_benchmark,
+    small_input_benchmark,
+    large_input_benchmark,
+    backend_benchmark,
+    no_avalanche_benchmark,
+    hashmap_benchmark,
+    derive_hash_benchmark
+);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+#![no_main]
+
+// Checks that splitting the input across `Xxh64::write` calls never changes the digest.
+//
+// The first byte is the number of chunk lengths that follow (mod 16), each one byte; the
+// rest of the input is the data, written in chunks of those lengths in turn until it runs
+// out. A length of 0 writes the rest of the data. Run with `cargo fuzz run write_chunks`
+// from the repository root.
+
+use libfuzzer_sys::fuzz_target;
+use xxh::{xxh64_slice, Xxh64};
+
+fuzz_target!(|input: &[u8]| {
+    let (count, rest) = match input.split_first() {
+        Some((count, rest)) => ((*count % 16) as usize, rest),
+        None => return,
+    };
+    if rest.len() < count {
+        return;
+    }
+    let (lengths, data) = rest.split_at(count);
+
+    let mut digest = Xxh64::with_seed(0);
+    let mut remaining = data;
+    let mut lengths = lengths.iter().cycle();
+    while !remaining.is_empty() {
+        let length = match lengths.next() {
+            Some(&length) if length > 0 => length as usize,
+            _ => remaining.len(),
+        };
+        let (chunk, tail) = remaining.split_at(length.min(remaining.len()));
+        digest.write(chunk);
+        // Empty writes must not disturb the buffer either.
+        digest.write(&[]);
+        remaining = tail;
+    }
+    assert_eq!(xxh64_slice(data, 0), digest.finish());
+});
@@ -0,0 +1,54 @@
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::reader::READER_CAPACITY;
+use crate::Xxh64;
+
+// xxh64_async_read hashes everything read from `reader` without blocking the executor.
+pub async fn xxh64_async_read<R: AsyncRead + Unpin>(mut reader: R, seed: u64) -> io::Result<u64> {
+    let mut buffer = vec![0; READER_CAPACITY];
+    let mut digest = Xxh64::with_seed(seed);
+    loop {
+        let n = match reader.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        digest.write(&buffer[..n]);
+    }
+    Ok(digest.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::xxh64_slice;
+
+    #[tokio::test]
+    async fn test_xxh64_async_read() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i * 7 + 3) as u8).collect();
+        assert_eq!(
+            xxh64_slice(&data, 10),
+            xxh64_async_read(Cursor::new(&data), 10).await.unwrap()
+        );
+        assert_eq!(
+            xxh64_slice(b"", 10),
+            xxh64_async_read(Cursor::new(b""), 10).await.unwrap()
+        );
+
+        // A small pipe delivers the input in many short reads.
+        let (mut client, server) = tokio::io::duplex(100);
+        let expected = xxh64_slice(&data, 10);
+        let writer = tokio::spawn(async move {
+            client.write_all(&data).await.unwrap();
+        });
+        assert_eq!(expected, xxh64_async_read(server, 10).await.unwrap());
+        writer.await.unwrap();
+    }
+}
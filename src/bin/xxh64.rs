@@ -1,46 +1,665 @@
+use std::collections::HashSet;
 use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::time::SystemTime;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
 
+// Default read buffer capacity, overridden with `--buffer-size`.
 const CAP: usize = 64 * 1024;
 
+// Minimum time between `--progress` updates.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const USAGE: &str = "\
+usage: xxh64 [--algo ALGO] [--buffer-size SIZE] [--seed SEED] [--json] [--progress]
+             [--recursive [--follow-symlinks]] [FILE]...
+       xxh64 [--algo ALGO] [--buffer-size SIZE] [--seed SEED] --check FILE
+ALGO is one of xxh32, xxh64 (the default), xxh3-64 or xxh3-128.";
+
+// UsageError is a malformed command line. It exits with status 2, other errors with 1.
+#[derive(Debug)]
+struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+fn usage_error<T>(message: String) -> Result<T, UsageError> {
+    Err(UsageError(message))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Algo {
+    Xxh32,
+    Xxh64,
+    Xxh3_64,
+    Xxh3_128,
+}
+
+impl Algo {
+    fn parse(name: &str) -> Option<Algo> {
+        match name {
+            "xxh32" => Some(Algo::Xxh32),
+            "xxh64" => Some(Algo::Xxh64),
+            "xxh3-64" => Some(Algo::Xxh3_64),
+            "xxh3-128" => Some(Algo::Xxh3_128),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Algo::Xxh32 => "xxh32",
+            Algo::Xxh64 => "xxh64",
+            Algo::Xxh3_64 => "xxh3-64",
+            Algo::Xxh3_128 => "xxh3-128",
+        }
+    }
+
+    // width is the number of hex digits in a digest.
+    fn width(self) -> usize {
+        match self {
+            Algo::Xxh32 => 8,
+            Algo::Xxh64 | Algo::Xxh3_64 => 16,
+            Algo::Xxh3_128 => 32,
+        }
+    }
+
+    // accepts_seed reports whether `seed` fits the algorithm's seed type.
+    fn accepts_seed(self, seed: u64) -> bool {
+        self != Algo::Xxh32 || seed <= u32::MAX as u64
+    }
+}
+
+struct Options {
+    algo: Algo,
+    buffer_size: usize,
+    check: Option<String>,
+    follow_symlinks: bool,
+    json: bool,
+    // Whether to report progress on stderr; only set when stderr is a terminal.
+    progress: bool,
+    recursive: bool,
+    seed: u64,
+    files: Vec<String>,
+}
+
 fn main() {
-    let start_time = SystemTime::now();
-    let args: Vec<String> = env::args().collect();
+    match run() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("xxh64: {}", e);
+            if e.is::<UsageError>() {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+            process::exit(1);
+        }
+    }
+}
 
-    let filename = &args[1];
-    let file = File::open(filename).expect("Invalid file path");
+// run returns whether every file could be hashed, or checked successfully. Failures on
+// individual files are reported as they happen; errors that stop the run are returned.
+fn run() -> Result<bool, Box<dyn Error>> {
+    let options = parse_args(env::args().skip(1))?;
+    if let Some(list) = &options.check {
+        return check(list, &options);
+    }
 
-    let mut reader = BufReader::with_capacity(CAP, file);
-    let mut digest = xxh::Xxh64::default();
-    loop {
-        let length = {
-            let data = reader.fill_buf();
-            match data {
-                Err(_) => break,
-                Ok(data) => {
-                    digest.write(data);
-                    data.len()
+    // A non-default algorithm or seed is recorded so the output can be checked, or
+    // reproduced, later.
+    if !options.json {
+        if options.algo != Algo::Xxh64 {
+            println!("# algo {}", options.algo.name());
+        }
+        if options.seed != 0 {
+            println!("# seed {:#x}", options.seed);
+        }
+    }
+    let mut failed = false;
+    let mut entries = Vec::new();
+    for path in targets(&options, &mut failed) {
+        let start = Instant::now();
+        match hash_file(
+            &path,
+            options.algo,
+            options.seed,
+            options.buffer_size,
+            options.progress,
+        ) {
+            Ok(result) if options.json => {
+                entries.push(json_entry(&path, &options, result, start.elapsed()))
+            }
+            Ok(result) => println!(
+                "{:0width$x}  {}",
+                result,
+                path.display(),
+                width = options.algo.width()
+            ),
+            Err(e) => {
+                eprintln!("xxh64: {}", e);
+                failed = true;
+            }
+        }
+    }
+    if options.json {
+        // A single file prints a bare object, several print an array of the files that
+        // could be hashed. Recursive runs always print an array.
+        if options.files.len() == 1 && !options.recursive {
+            if let Some(entry) = entries.first() {
+                println!("{}", entry);
+            }
+        } else {
+            println!("[{}]", entries.join(", "));
+        }
+    }
+    Ok(!failed)
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, UsageError> {
+    let mut options = Options {
+        algo: Algo::Xxh64,
+        buffer_size: CAP,
+        check: None,
+        follow_symlinks: false,
+        json: false,
+        progress: false,
+        recursive: false,
+        seed: 0,
+        files: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => Ok(value),
+            None => usage_error(format!("option '{}' requires a value", arg)),
+        };
+        match arg.as_str() {
+            "--check" => {
+                let list = value()?;
+                if options.check.is_some() {
+                    return usage_error(String::from("--check may only be given once"));
+                }
+                options.check = Some(list);
+            }
+            "--buffer-size" => options.buffer_size = buffer_size(&value()?)?,
+            "--algo" => options.algo = algo_arg(&value()?)?,
+            "--follow-symlinks" => options.follow_symlinks = true,
+            "--json" => options.json = true,
+            "--progress" => options.progress = true,
+            "--recursive" => options.recursive = true,
+            "--seed" => options.seed = seed_arg(&value()?)?,
+            _ => options.files.push(arg),
+        }
+    }
+
+    if options.check.is_some() && (options.json || options.recursive || !options.files.is_empty()) {
+        return usage_error(String::from(
+            "--check cannot be combined with --json, --recursive or files",
+        ));
+    }
+    if !options.algo.accepts_seed(options.seed) {
+        return usage_error(format!(
+            "seed {:#x} does not fit in 32 bits for xxh32",
+            options.seed
+        ));
+    }
+    if options.files.is_empty() {
+        options.files.push(String::from("-"));
+    }
+    // Progress updates would only clutter redirected output.
+    options.progress &= io::stderr().is_terminal();
+    Ok(options)
+}
+
+// algo_arg parses an `--algo` argument.
+fn algo_arg(arg: &str) -> Result<Algo, UsageError> {
+    match Algo::parse(arg) {
+        Some(algo) => Ok(algo),
+        None => usage_error(format!(
+            "unknown algorithm '{}': expected xxh32, xxh64, xxh3-64 or xxh3-128",
+            arg
+        )),
+    }
+}
+
+// buffer_size parses a `--buffer-size` argument.
+fn buffer_size(arg: &str) -> Result<usize, UsageError> {
+    match parse_size(arg) {
+        Some(size) => Ok(size),
+        None => usage_error(format!(
+            "invalid buffer size '{}': expected a positive number of bytes, optionally suffixed with K, M or G",
+            arg
+        )),
+    }
+}
+
+// seed_arg parses a `--seed` argument.
+fn seed_arg(arg: &str) -> Result<u64, UsageError> {
+    match parse_seed(arg) {
+        Some(seed) => Ok(seed),
+        None => usage_error(format!(
+            "invalid seed '{}': expected a decimal or 0x-prefixed hexadecimal 64-bit integer",
+            arg
+        )),
+    }
+}
+
+// parse_seed parses a decimal seed, or a hexadecimal one prefixed with `0x`.
+fn parse_seed(arg: &str) -> Option<u64> {
+    let (digits, radix) = match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (arg, 10),
+    };
+    // from_str_radix accepts a leading `+`, which is not a digit.
+    if digits.starts_with('+') {
+        return None;
+    }
+    u64::from_str_radix(digits, radix).ok()
+}
+
+// parse_size parses a non-zero byte count such as `4096`, `512K` or `1M`.
+// Suffixes are binary multiples and case-insensitive.
+fn parse_size(arg: &str) -> Option<usize> {
+    let (digits, shift) = match arg.char_indices().last()? {
+        (i, 'k') | (i, 'K') => (&arg[..i], 10),
+        (i, 'm') | (i, 'M') => (&arg[..i], 20),
+        (i, 'g') | (i, 'G') => (&arg[..i], 30),
+        _ => (arg, 0),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let size = digits.parse::<usize>().ok()?.checked_mul(1 << shift)?;
+    if size == 0 {
+        return None;
+    }
+    Some(size)
+}
+
+// check verifies every `<hex>  <path>` line of the checksum list,
+// returning whether all entries matched. `# algo <ALGO>` and `# seed <SEED>`
+// lines set the algorithm and seed for the entries that follow them.
+fn check(list: &str, options: &Options) -> Result<bool, Box<dyn Error>> {
+    let content = fs::read_to_string(list)
+        .map_err(|e| format!("cannot read '{}': {}", list, describe(&e)))?;
+
+    let mut ok = true;
+    let mut algo = options.algo;
+    let mut seed = options.seed;
+    for line in content.lines().filter(|line| !line.is_empty()) {
+        if let Some(arg) = line.strip_prefix("# algo ") {
+            match Algo::parse(arg) {
+                Some(a) => algo = a,
+                None => {
+                    eprintln!("xxh64: {}: unknown algorithm '{}'", list, arg);
+                    ok = false;
                 }
             }
+            continue;
+        }
+        if let Some(arg) = line.strip_prefix("# seed ") {
+            match parse_seed(arg) {
+                Some(s) => seed = s,
+                None => {
+                    eprintln!("xxh64: {}: invalid seed '{}'", list, arg);
+                    ok = false;
+                }
+            }
+            continue;
+        }
+        let (expected, filename) = match parse_check_line(line, algo.width()) {
+            Some(entry) => entry,
+            None => {
+                eprintln!("xxh64: {}: improperly formatted checksum line", list);
+                ok = false;
+                continue;
+            }
+        };
+        if !algo.accepts_seed(seed) {
+            eprintln!(
+                "xxh64: {}: seed {:#x} does not fit in 32 bits for xxh32",
+                list, seed
+            );
+            ok = false;
+            continue;
+        }
+        match hash_file(
+            Path::new(filename),
+            algo,
+            seed,
+            options.buffer_size,
+            options.progress,
+        ) {
+            Ok(result) if result == expected => println!("{}: OK", filename),
+            Ok(_) => {
+                println!("{}: FAILED", filename);
+                ok = false;
+            }
+            Err(_) => {
+                println!("{}: FAILED open or read", filename);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn parse_check_line(line: &str, width: usize) -> Option<(u128, &str)> {
+    let (hex, filename) = line.split_once("  ")?;
+    if hex.len() != width || filename.is_empty() {
+        return None;
+    }
+    let expected = u128::from_str_radix(hex, 16).ok()?;
+    Some((expected, filename))
+}
+
+fn json_entry(path: &Path, options: &Options, result: u128, elapsed: Duration) -> String {
+    format!(
+        "{{\"file\": {}, \"algo\": \"{}\", \"seed\": {}, \"dec\": {}, \"hex\": \"{:0width$x}\", \"seconds\": {}}}",
+        json_string(&path.to_string_lossy()),
+        options.algo.name(),
+        options.seed,
+        result,
+        result,
+        elapsed.as_secs_f64(),
+        width = options.algo.width()
+    )
+}
+
+// json_string quotes `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// targets lists the files to hash. With `--recursive`, directories are replaced by the
+// regular files below them, sorted by path; walk errors are reported and set `failed`.
+fn targets(options: &Options, failed: &mut bool) -> Vec<PathBuf> {
+    let mut targets = Vec::new();
+    for filename in &options.files {
+        let path = PathBuf::from(filename);
+        if options.recursive && filename != "-" && path.is_dir() {
+            let mut files = Vec::new();
+            let mut visited = HashSet::new();
+            walk(
+                &path,
+                options.follow_symlinks,
+                &mut visited,
+                &mut files,
+                failed,
+            );
+            files.sort();
+            targets.extend(files);
+        } else {
+            targets.push(path);
+        }
+    }
+    targets
+}
+
+fn walk(
+    dir: &Path,
+    follow_symlinks: bool,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+    failed: &mut bool,
+) {
+    let mut report = |path: &Path, e: io::Error| {
+        eprintln!("xxh64: cannot read '{}': {}", path.display(), describe(&e));
+        *failed = true;
+    };
+
+    if follow_symlinks {
+        // A followed link can lead back to a directory that is already being walked.
+        match fs::canonicalize(dir) {
+            Ok(real) => {
+                if !visited.insert(real) {
+                    return;
+                }
+            }
+            Err(e) => return report(dir, e),
+        }
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return report(dir, e),
+    };
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                report(dir, e);
+                continue;
+            }
+        };
+        let path = entry.path();
+        let mut file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(e) => {
+                report(&path, e);
+                continue;
+            }
+        };
+        if file_type.is_symlink() {
+            if !follow_symlinks {
+                continue;
+            }
+            file_type = match fs::metadata(&path) {
+                Ok(metadata) => metadata.file_type(),
+                Err(e) => {
+                    report(&path, e);
+                    continue;
+                }
+            };
+        }
+        if file_type.is_dir() {
+            dirs.push(path);
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    // Walk in a fixed order so that which copy of a linked directory is kept is stable.
+    dirs.sort();
+    for dir in dirs {
+        walk(&dir, follow_symlinks, visited, files, failed);
+    }
+}
+
+// hash_file hashes the named file, or standard input if the name is `-`, reporting
+// progress on stderr if `progress` is set. Errors name the file and whether it could not
+// be opened or read.
+fn hash_file(
+    path: &Path,
+    algo: Algo,
+    seed: u64,
+    buffer_size: usize,
+    progress: bool,
+) -> io::Result<u128> {
+    let context = |action: &str, e: io::Error| {
+        io::Error::new(
+            e.kind(),
+            format!("{} '{}': {}", action, path.display(), describe(&e)),
+        )
+    };
+    let result = if path == Path::new("-") {
+        let stdin = io::stdin();
+        if progress {
+            hash_reader(
+                Progress::new(stdin.lock(), path, None),
+                algo,
+                seed,
+                buffer_size,
+            )
+        } else {
+            hash_reader(stdin.lock(), algo, seed, buffer_size)
+        }
+    } else {
+        let file = File::open(path).map_err(|e| context("cannot open", e))?;
+        if progress {
+            // Only regular files have a size to measure progress against.
+            let total = file
+                .metadata()
+                .ok()
+                .filter(|m| m.is_file())
+                .map(|m| m.len());
+            hash_reader(Progress::new(file, path, total), algo, seed, buffer_size)
+        } else {
+            hash_reader(file, algo, seed, buffer_size)
+        }
+    };
+    result.map_err(|e| context("error reading", e))
+}
+
+// Progress counts the bytes read through it and shows a status line on stderr: the
+// percentage done and throughput if the total size is known, or the bytes read and
+// throughput if not. The line is cleared once hashing ends.
+struct Progress<'a, R> {
+    inner: R,
+    name: &'a Path,
+    total: Option<u64>,
+    done: u64,
+    start: Instant,
+    last_update: Instant,
+}
+
+impl<'a, R: Read> Progress<'a, R> {
+    fn new(inner: R, name: &'a Path, total: Option<u64>) -> Progress<'a, R> {
+        let now = Instant::now();
+        Progress {
+            inner,
+            name,
+            total,
+            done: 0,
+            start: now,
+            last_update: now,
+        }
+    }
+
+    fn update(&self) {
+        let rate = self.done as f64 / self.start.elapsed().as_secs_f64();
+        match self.total {
+            Some(total) if total > 0 => eprint!(
+                "\r\x1b[2K{}: {}% {}/s",
+                self.name.display(),
+                (self.done.min(total) as u128 * 100 / total as u128),
+                format_bytes(rate)
+            ),
+            _ => eprint!(
+                "\r\x1b[2K{}: {} {}/s",
+                self.name.display(),
+                format_bytes(self.done as f64),
+                format_bytes(rate)
+            ),
+        }
+    }
+}
+
+impl<'a, R: Read> Read for Progress<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.done += n as u64;
+        if self.last_update.elapsed() >= PROGRESS_INTERVAL {
+            self.update();
+            self.last_update = Instant::now();
+        }
+        Ok(n)
+    }
+}
+
+impl<'a, R> Drop for Progress<'a, R> {
+    fn drop(&mut self) {
+        // Only clear the line if an update was shown.
+        if self.last_update != self.start {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+// format_bytes formats a byte count with a binary unit, e.g. `1.5 GiB`.
+fn format_bytes(bytes: f64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, units[unit])
+}
+
+// describe formats an IO error for users, without the `(os error N)` suffix.
+fn describe(e: &io::Error) -> String {
+    let message = e.to_string();
+    match e.raw_os_error() {
+        Some(code) => message
+            .trim_end_matches(&format!(" (os error {})", code))
+            .to_string(),
+        None => message,
+    }
+}
+
+fn hash_reader<R: Read>(reader: R, algo: Algo, seed: u64, buffer_size: usize) -> io::Result<u128> {
+    let mut reader = BufReader::with_capacity(buffer_size, reader);
+    match algo {
+        Algo::Xxh64 => xxh::xxh64_bufread(&mut reader, seed).map(u128::from),
+        Algo::Xxh32 => {
+            // The seed was checked against `accepts_seed` by the caller.
+            let mut digest = xxh::Xxh32::with_seed(seed as u32);
+            for_each_chunk(&mut reader, |data| digest.write(data))?;
+            Ok(digest.finish().into())
+        }
+        Algo::Xxh3_64 => {
+            let mut digest = xxh::Xxh3_64::with_seed(seed);
+            for_each_chunk(&mut reader, |data| digest.write(data))?;
+            Ok(digest.finish().into())
+        }
+        Algo::Xxh3_128 => {
+            let mut digest = xxh::Xxh128::with_seed(seed);
+            for_each_chunk(&mut reader, |data| digest.write(data))?;
+            Ok(digest.finish())
+        }
+    }
+}
+
+// for_each_chunk passes the rest of `reader` to `f`, one buffer at a time, like
+// `xxh::xxh64_bufread` does for XXH64.
+fn for_each_chunk<R: BufRead, F: FnMut(&[u8])>(reader: &mut R, mut f: F) -> io::Result<()> {
+    loop {
+        let length = {
+            let data = match reader.fill_buf() {
+                Ok(data) => data,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            f(data);
+            data.len()
         };
         if length == 0 {
-            break;
+            return Ok(());
         }
         reader.consume(length)
     }
-    let result = digest.finish();
-    println!(
-        "Finished `{}` in {}s\r\n\
-    DEC: {}\r\n\
-    HEX: {:x}",
-        filename,
-        SystemTime::now()
-            .duration_since(start_time)
-            .expect("Invalid system time")
-            .as_secs_f32(),
-        result,
-        result
-    );
 }
@@ -0,0 +1,93 @@
+// C-compatible entry points mirroring the shape of the reference xxHash API.
+// Build a C library with e.g. `cargo rustc --release --features ffi --crate-type cdylib`.
+//
+// Pointer contracts: a data pointer may be null only when its length is zero.
+// A state pointer must come from `xxh64_state_create` and not have been freed.
+
+use core::slice;
+
+use crate::{xxh64_slice, Xxh64};
+
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Hashes `len` bytes starting at `ptr`.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes. It may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn xxh64_oneshot(ptr: *const u8, len: usize, seed: u64) -> u64 {
+    xxh64_slice(bytes(ptr, len), seed)
+}
+
+/// Allocates a streaming state. Release it with `xxh64_state_free`.
+#[no_mangle]
+pub extern "C" fn xxh64_state_create(seed: u64) -> *mut Xxh64 {
+    Box::into_raw(Box::new(Xxh64::with_seed(seed)))
+}
+
+/// Feeds `len` bytes starting at `ptr` into the state.
+///
+/// # Safety
+///
+/// `state` must come from `xxh64_state_create` and not have been freed.
+/// `ptr` must be valid for reads of `len` bytes. It may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn xxh64_state_update(state: *mut Xxh64, ptr: *const u8, len: usize) {
+    (*state).write(bytes(ptr, len));
+}
+
+/// Returns the digest of the bytes fed so far. The state remains usable.
+///
+/// # Safety
+///
+/// `state` must come from `xxh64_state_create` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn xxh64_state_digest(state: *const Xxh64) -> u64 {
+    (*state).finish()
+}
+
+/// Releases a state. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `state` must be null or come from `xxh64_state_create` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn xxh64_state_free(state: *mut Xxh64) {
+    if !state.is_null() {
+        drop(Box::from_raw(state));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use super::*;
+
+    #[test]
+    fn test_ffi() {
+        let data: Vec<u8> = (0..100).map(|i| i as u8).collect();
+        unsafe {
+            assert_eq!(
+                xxh64_slice(&data, 10),
+                xxh64_oneshot(data.as_ptr(), data.len(), 10)
+            );
+            assert_eq!(xxh64_slice(b"", 10), xxh64_oneshot(ptr::null(), 0, 10));
+
+            let state = xxh64_state_create(10);
+            xxh64_state_update(state, data.as_ptr(), 40);
+            xxh64_state_update(state, ptr::null(), 0);
+            xxh64_state_update(state, data[40..].as_ptr(), 60);
+            assert_eq!(xxh64_slice(&data, 10), xxh64_state_digest(state));
+            xxh64_state_free(state);
+            xxh64_state_free(ptr::null_mut());
+        }
+    }
+}
@@ -1,6 +1,70 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// The hashing code must not panic on any input; tests may still unwrap (see clippy.toml).
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use core::cell::Cell;
+use core::convert::TryInto;
+use core::fmt;
 use core::hash::BuildHasher;
-use std::convert::TryInto;
-use std::hash::Hasher;
+use core::hash::Hash;
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "tokio")]
+mod async_reader;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "memmap")]
+mod mmap;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod phf;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "digest")]
+mod rustcrypto;
+#[cfg(feature = "bincode")]
+mod serde_hash;
+#[cfg(feature = "unstable-simd")]
+mod simd;
+mod window;
+#[cfg(feature = "std")]
+mod writer;
+mod xxh3;
+mod xxh32;
+
+#[cfg(feature = "tokio")]
+pub use async_reader::xxh64_async_read;
+#[cfg(feature = "memmap")]
+pub use mmap::xxh64_mmap;
+#[cfg(feature = "parallel")]
+pub use parallel::{xxh64_combine_chunks, xxh64_slice_parallel};
+pub use phf::XxhPhf;
+#[cfg(feature = "std")]
+pub use reader::{
+    xxh64_bufread, xxh64_file, xxh64_reader, xxh64_reader_sized, xxh64_reader_with_buf,
+    xxh64_reader_with_capacity,
+};
+#[cfg(feature = "digest")]
+pub use rustcrypto::Xxh64Digest;
+#[cfg(feature = "bincode")]
+pub use serde_hash::xxh64_serde;
+pub use window::WindowHasher;
+#[cfg(feature = "std")]
+pub use writer::{HashingWriter, VerifyingWriter};
+#[cfg(feature = "std")]
+pub use xxh3::xxh3_128_hex;
+pub use xxh3::{
+    xxh128_canonical, xxh128_cmp, xxh128_from_canonical, xxh3_128_slice, xxh3_256, xxh3_64_and_128,
+    xxh3_64_slice, xxh3_64_with_secret, xxh3_64_with_secret_and_seed, xxh3_generate_secret, Xxh128,
+    Xxh3_64,
+};
+#[cfg(feature = "std")]
+pub use xxh32::xxh32_hex;
+pub use xxh32::{
+    xxh32_canonical, xxh32_from_canonical, xxh32_slice, xxh32_str, Xxh32, Xxh32BuildHasher,
+};
 
 const PRIME64_1: u64 = 0x9E3779B185EBCA87;
 const PRIME64_2: u64 = 0xC2B2AE3D27D4EB4F;
@@ -10,9 +74,34 @@ const PRIME64_5: u64 = 0x27D4EB2F165667C5;
 
 const STRIPE_LEN_32: usize = 32;
 
-pub fn xxh64_str(s: String, seed: u64) -> u64 {
-    let slice = s.as_bytes();
-    xxh64_slice(slice, seed)
+// Size of the state saved by `Xxh64::save_state`: the seed, the four accumulators, the
+// stripe buffer and the input length.
+pub const XXH64_SAVED_STATE_LEN: usize = 8 + 4 * 8 + STRIPE_LEN_32 + 8;
+
+// XxHasher is the interface shared by the streaming digests, for code that is generic over
+// the algorithm.
+pub trait XxHasher {
+    type Output;
+
+    fn with_seed(seed: u64) -> Self;
+    fn update(&mut self, bytes: &[u8]);
+    fn digest(&self) -> Self::Output;
+}
+
+// hash is a shorthand for `xxh64_slice` with seed 0.
+pub fn hash(bytes: &[u8]) -> u64 {
+    xxh64_slice(bytes, 0)
+}
+
+// xxh64 hashes anything that can be viewed as bytes: `&str`, `String`, `Vec<u8>`,
+// `Cow<[u8]>`, byte arrays and so on. `xxh64_str` and `xxh64_slice` remain for
+// existing callers.
+pub fn xxh64<T: AsRef<[u8]>>(data: T, seed: u64) -> u64 {
+    xxh64_slice(data.as_ref(), seed)
+}
+
+pub fn xxh64_str(s: &str, seed: u64) -> u64 {
+    xxh64(s, seed)
 }
 
 pub fn xxh64_slice(mut slice: &[u8], seed: u64) -> u64 {
@@ -20,38 +109,129 @@ pub fn xxh64_slice(mut slice: &[u8], seed: u64) -> u64 {
 
     let input_len = slice.len();
 
+    if input_len <= 16 {
+        return xxh64_short(slice, seed);
+    }
     if slice.len() < 32 {
         // Special case: input is less than 32 bytes.
         // The algorithm then proceeds directly to step 4.
-        acc = seed + PRIME64_5;
+        acc = seed.wrapping_add(PRIME64_5);
     } else {
         // Step 1. Initialise internal accumulators
-        let mut acc1: u64 = seed + PRIME64_1.wrapping_add(PRIME64_2);
-        let mut acc2: u64 = seed + PRIME64_2;
+        let accs = (
+            seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+            seed.wrapping_add(PRIME64_2),
+            seed,
+            seed.wrapping_sub(PRIME64_1),
+        );
+        // Step 2. Process stripes
+        let stripes_len = slice.len() - slice.len() % STRIPE_LEN_32;
+        let (acc1, acc2, acc3, acc4) = process_stripes(accs, &slice[..stripes_len]);
+        slice = &slice[stripes_len..];
+        // Step 3. Accumulator convergence
+        acc = acc1
+            .rotate_left(1)
+            .wrapping_add(acc2.rotate_left(7))
+            .wrapping_add(acc3.rotate_left(12))
+            .wrapping_add(acc4.rotate_left(18));
+        acc = merge_accumulator(acc, acc1);
+        acc = merge_accumulator(acc, acc2);
+        acc = merge_accumulator(acc, acc3);
+        acc = merge_accumulator(acc, acc4);
+    }
+    finalize(acc, input_len as u64, slice)
+}
+
+// xxh64_slices returns the hash of the concatenation of `fragments`, without copying
+// them into one buffer.
+pub fn xxh64_slices(fragments: &[&[u8]], seed: u64) -> u64 {
+    let mut digest = Xxh64::with_seed(seed);
+    for fragment in fragments {
+        digest.write(fragment);
+    }
+    digest.finish()
+}
+
+// xxh64_u64_slice hashes `values` as their little-endian bytes, so the result is the same
+// on every host. On little-endian hosts the slice is hashed in place.
+pub fn xxh64_u64_slice(values: &[u64], seed: u64) -> u64 {
+    #[cfg(target_endian = "little")]
+    {
+        // SAFETY: u64 has no padding and u8 no alignment requirement, so the memory of
+        // `values` is valid as `values.len() * 8` bytes for as long as `values` is borrowed.
+        let bytes =
+            unsafe { core::slice::from_raw_parts(values.as_ptr() as *const u8, values.len() * 8) };
+        xxh64_slice(bytes, seed)
+    }
+    #[cfg(not(target_endian = "little"))]
+    {
+        xxh64_u64_slice_swapped(values, seed)
+    }
+}
+
+// The big-endian path of `xxh64_u64_slice`, converting each value as it is written.
+#[cfg_attr(all(target_endian = "little", not(test)), allow(dead_code))]
+fn xxh64_u64_slice_swapped(values: &[u64], seed: u64) -> u64 {
+    let mut digest = Xxh64::with_seed(seed);
+    for value in values {
+        digest.write_u64(*value);
+    }
+    digest.finish()
+}
+
+// xxh64_framed hashes a sequence of fields so that the field boundaries count: each field
+// is written as its length, a little-endian u64, followed by its bytes. Unlike with
+// `xxh64_slices`, ["ab", "c"] and ["a", "bc"] hash differently. This is a construction on
+// top of XXH64, not standard xxHash, so other implementations must frame the fields the
+// same way to agree.
+pub fn xxh64_framed(fields: &[&[u8]], seed: u64) -> u64 {
+    let mut digest = Xxh64::with_seed(seed);
+    for field in fields {
+        digest.write_u64(field.len() as u64);
+        digest.write(field);
+    }
+    digest.finish()
+}
+
+// xxh64_unordered hashes a multiset of values independently of their order, e.g. the
+// per-entry hashes of a `HashMap`. Each item is hashed on its own and the results are
+// combined with a wrapping sum, so equal items do not cancel out as they would with XOR;
+// the sum and the item count are then hashed once more. Order independence costs some
+// collision resistance: sets with equal sums collide, which is much easier to arrange
+// than a collision of `xxh64_slice` over the items in sequence.
+pub fn xxh64_unordered<I: IntoIterator<Item = u64>>(items: I, seed: u64) -> u64 {
+    let mut sum = 0u64;
+    let mut count = 0u64;
+    for item in items {
+        sum = sum.wrapping_add(xxh64_slice(&item.to_le_bytes(), seed));
+        count = count.wrapping_add(1);
+    }
+    let mut digest = Xxh64::with_seed(seed);
+    digest.write_u64(sum);
+    digest.write_u64(count);
+    digest.finish()
+}
+
+// xxh64_const is equivalent to `xxh64_slice`, but can be evaluated at compile time.
+pub const fn xxh64_const(bytes: &[u8], seed: u64) -> u64 {
+    let input_len = bytes.len();
+    let mut offset = 0;
+    let mut acc: u64;
+
+    if input_len < STRIPE_LEN_32 {
+        acc = seed.wrapping_add(PRIME64_5);
+    } else {
+        let mut acc1: u64 = seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2);
+        let mut acc2: u64 = seed.wrapping_add(PRIME64_2);
         let mut acc3: u64 = seed;
         let mut acc4: u64 = seed.wrapping_sub(PRIME64_1);
-        // Step 2. Process stripes
-        while slice.len() >= 32 {
-            // Each lane read its associated 64-bit value using little-endian convention.
-            acc1 = round(
-                acc1,
-                u64::from_le_bytes(slice[0..8].try_into().expect("incorrect length")),
-            );
-            acc2 = round(
-                acc2,
-                u64::from_le_bytes(slice[8..16].try_into().expect("incorrect length")),
-            );
-            acc3 = round(
-                acc3,
-                u64::from_le_bytes(slice[16..24].try_into().expect("incorrect length")),
-            );
-            acc4 = round(
-                acc4,
-                u64::from_le_bytes(slice[24..32].try_into().expect("incorrect length")),
-            );
-            slice = &slice[32..slice.len()]
+        while input_len - offset >= STRIPE_LEN_32 {
+            acc1 = round(acc1, read_u64_const(bytes, offset));
+            acc2 = round(acc2, read_u64_const(bytes, offset + 8));
+            acc3 = round(acc3, read_u64_const(bytes, offset + 16));
+            acc4 = round(acc4, read_u64_const(bytes, offset + 24));
+            offset += STRIPE_LEN_32;
         }
-        // Step 3. Accumulator convergence
         acc = acc1
             .rotate_left(1)
             .wrapping_add(acc2.rotate_left(7))
@@ -62,30 +242,26 @@ pub fn xxh64_slice(mut slice: &[u8], seed: u64) -> u64 {
         acc = merge_accumulator(acc, acc3);
         acc = merge_accumulator(acc, acc4);
     }
-    // Step 4. Add input length
     acc = acc.wrapping_add(input_len as u64);
-    // Step 5. Consume remaining input
-    while slice.len() >= 8 {
-        let lane = u64::from_le_bytes(slice[0..8].try_into().expect("incorrect length"));
-        acc ^= round(0u64, lane);
+    while input_len - offset >= 8 {
+        acc ^= round(0u64, read_u64_const(bytes, offset));
         acc = (acc.rotate_left(27)).wrapping_mul(PRIME64_1);
         acc = acc.wrapping_add(PRIME64_4);
-        slice = &slice[8..slice.len()]
+        offset += 8;
     }
-    if slice.len() >= 4 {
-        let lane = u32::from_le_bytes(slice[0..4].try_into().expect("incorrect length")) as u64;
+    if input_len - offset >= 4 {
+        let lane = read_u32_const(bytes, offset) as u64;
         acc ^= lane.wrapping_mul(PRIME64_1);
         acc = acc.rotate_left(23).wrapping_mul(PRIME64_2);
         acc = acc.wrapping_add(PRIME64_3);
-        slice = &slice[4..slice.len()]
+        offset += 4;
     }
-    while slice.len() >= 1 {
-        let lane = slice[0] as u64;
+    while offset < input_len {
+        let lane = bytes[offset] as u64;
         acc ^= lane.wrapping_mul(PRIME64_5);
         acc = acc.rotate_left(11).wrapping_mul(PRIME64_1);
-        slice = &slice[1..slice.len()]
+        offset += 1;
     }
-    // Step 6. Final mix (avalanche)
     acc ^= acc >> 33;
     acc = acc.wrapping_mul(PRIME64_2);
     acc ^= acc >> 29;
@@ -94,10 +270,116 @@ pub fn xxh64_slice(mut slice: &[u8], seed: u64) -> u64 {
     acc
 }
 
+// xxh64_collides reports whether `a` and `b` hash the same under `seed`, so a set of
+// interned strings can be checked at compile time:
+//
+//     const _: () = assert!(!xxh64_collides(b"foo", b"bar", 0));
+//
+// Equal inputs always collide.
+pub const fn xxh64_collides(a: &[u8], b: &[u8], seed: u64) -> bool {
+    xxh64_const(a, seed) == xxh64_const(b, seed)
+}
+
+// `from_le_bytes` with `try_into` is not usable in const fn, so read little-endian lanes by hand.
+#[inline(always)]
+const fn read_u64_const(bytes: &[u8], offset: usize) -> u64 {
+    read_u32_const(bytes, offset) as u64 | (read_u32_const(bytes, offset + 4) as u64) << 32
+}
+
+#[inline(always)]
+const fn read_u32_const(bytes: &[u8], offset: usize) -> u32 {
+    bytes[offset] as u32
+        | (bytes[offset + 1] as u32) << 8
+        | (bytes[offset + 2] as u32) << 16
+        | (bytes[offset + 3] as u32) << 24
+}
+
+pub fn xxh64_canonical(hash: u64) -> [u8; 8] {
+    // The canonical representation is big-endian, matching `XXH64_canonicalFromHash`.
+    hash.to_be_bytes()
+}
+
+pub fn xxh64_from_canonical(bytes: &[u8; 8]) -> u64 {
+    u64::from_be_bytes(*bytes)
+}
+
+// append_xxh64_footer appends the canonical XXH64 of `data` to it, as a checksum footer
+// for `verify_and_strip_xxh64_footer`.
+#[cfg(feature = "std")]
+pub fn append_xxh64_footer(data: &mut Vec<u8>, seed: u64) {
+    let footer = xxh64_canonical(xxh64_slice(data, seed));
+    data.extend_from_slice(&footer);
+}
+
+// verify_and_strip_xxh64_footer returns the payload of `data` without its 8-byte footer
+// if the footer is the canonical XXH64 of the payload, as written by
+// `append_xxh64_footer`. It returns `None` for corrupted data or data too short to have
+// a footer.
+pub fn verify_and_strip_xxh64_footer(data: &[u8], seed: u64) -> Option<&[u8]> {
+    let (payload, footer) = data.split_at(data.len().checked_sub(8)?);
+    let mut canonical = [0; 8];
+    canonical.copy_from_slice(footer);
+    if xxh64_slice(payload, seed) == xxh64_from_canonical(&canonical) {
+        Some(payload)
+    } else {
+        None
+    }
+}
+
+// xxh64_hex returns `xxh64_slice(bytes, seed)` as 16 lowercase hex digits, keeping
+// leading zeros.
+#[cfg(feature = "std")]
+pub fn xxh64_hex(bytes: &[u8], seed: u64) -> String {
+    format!("{:016x}", xxh64_slice(bytes, seed))
+}
+
+// xxh64_to_f64 maps a hash to a uniform float in [0, 1), using the top 53 bits as the
+// mantissa.
+pub fn xxh64_to_f64(hash: u64) -> f64 {
+    (hash >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+// xxh64_bucket assigns `key` to one of `num_buckets` buckets with Lamping and Veach's jump
+// consistent hash, seeded by the key's XXH64. Growing from n to n + 1 buckets moves only
+// about 1 / (n + 1) of the keys, all of them into the new bucket.
+// It panics if `num_buckets` is 0.
+pub fn xxh64_bucket(key: &[u8], seed: u64, num_buckets: u32) -> u32 {
+    assert!(num_buckets > 0, "num_buckets must not be 0");
+    let mut state = xxh64_slice(key, seed);
+    let mut bucket = 0;
+    let mut next = 0;
+    while next < num_buckets as i64 {
+        bucket = next;
+        state = state.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((state >> 33) + 1) as f64)) as i64;
+    }
+    bucket as u32
+}
+
+// xxh64_to_unit_interval is a shorthand for `xxh64_to_f64(xxh64_slice(bytes, seed))`.
+pub fn xxh64_to_unit_interval(bytes: &[u8], seed: u64) -> f64 {
+    xxh64_to_f64(xxh64_slice(bytes, seed))
+}
+
+// xxh64_verify_ct compares two digests in constant time, so a verification endpoint does
+// not reveal through its timing how many leading bits of a guess were right. This only
+// removes that oracle: xxHash is not cryptographic, and anyone who can choose the input
+// can find collisions, so it must not be used where forgery matters.
+pub fn xxh64_verify_ct(a: u64, b: u64) -> bool {
+    // black_box keeps the optimizer from turning this back into an early-exit compare.
+    let diff = core::hint::black_box(a ^ b);
+    // The top bit of `diff | -diff` is set exactly when some bit of `diff` is.
+    (diff | diff.wrapping_neg()) >> 63 == 0
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(align(8))]
 struct Align64<T>(T);
 
 // Xxh64 represents the xxHash digest algorithm(64-bits).
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Xxh64 {
     seed: u64,
     acc1: u64,
@@ -106,62 +388,240 @@ pub struct Xxh64 {
     acc4: u64,
     buffer: Align64<[u8; STRIPE_LEN_32]>,
     buffer_len: usize,
-    input_len: usize,
+    // u64 rather than usize, so streams longer than 4 GiB hash correctly on 32-bit targets.
+    input_len: u64,
+    // Set by `with_config` when the final avalanche is disabled.
+    #[cfg_attr(feature = "serde", serde(default))]
+    skip_avalanche: bool,
+}
+
+// Xxh64Config holds the options of `Xxh64::with_config`. The default is plain XXH64 with
+// seed 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Xxh64Config {
+    pub seed: u64,
+    // avalanche enables the final mix of `finish`. Without it, `finish` is cheaper but its
+    // output is no longer XXH64: nearby inputs give nearby outputs, and the low bits are
+    // poorly distributed. Only turn it off for keys that are already well distributed,
+    // such as pre-hashed IDs, in tables that can tolerate that.
+    pub avalanche: bool,
+}
+
+impl Default for Xxh64Config {
+    fn default() -> Self {
+        Xxh64Config {
+            seed: 0,
+            avalanche: true,
+        }
+    }
+}
+
+// Xxh64State is a checkpoint of an `Xxh64` stream taken with `Xxh64::into_state`.
+// Together with the number of bytes written so far, it lets the same stream be resumed
+// later, possibly in another process. It cannot combine digests of independent streams.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Xxh64State {
+    pub seed: u64,
+    pub accs: [u64; 4],
+    // Bytes of the current, incomplete stripe; only the first `input_len % 32` are used.
+    pub buffer: [u8; STRIPE_LEN_32],
 }
 
 impl Xxh64 {
     pub fn with_seed(seed: u64) -> Xxh64 {
         Xxh64 {
             seed,
-            acc1: seed + PRIME64_1.wrapping_add(PRIME64_2),
-            acc2: seed + PRIME64_2,
+            acc1: seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+            acc2: seed.wrapping_add(PRIME64_2),
             acc3: seed,
             acc4: seed.wrapping_sub(PRIME64_1),
             buffer: Align64([0; STRIPE_LEN_32]),
             buffer_len: 0,
             input_len: 0,
+            skip_avalanche: false,
+        }
+    }
+
+    // with_random_seed creates a digest with a seed drawn from the OS random number
+    // generator, so that hashes of attacker-controlled keys cannot be predicted, as with
+    // std's `RandomState`. The seed is available from `seed`. It panics if the OS
+    // generator fails.
+    #[cfg(feature = "getrandom")]
+    pub fn with_random_seed() -> Xxh64 {
+        Xxh64::with_seed(random_seed())
+    }
+
+    // hash_iter hashes the bytes produced by an iterator, gathering them into
+    // stripes before writing.
+    pub fn hash_iter<I: IntoIterator<Item = u8>>(iter: I, seed: u64) -> u64 {
+        let mut digest = Xxh64::with_seed(seed);
+        let mut stripe = [0u8; STRIPE_LEN_32];
+        let mut stripe_len = 0;
+        for byte in iter {
+            stripe[stripe_len] = byte;
+            stripe_len += 1;
+            if stripe_len == STRIPE_LEN_32 {
+                digest.write(&stripe);
+                stripe_len = 0;
+            }
+        }
+        digest.write(&stripe[..stripe_len]);
+        digest.finish()
+    }
+
+    pub fn reset(&mut self) {
+        self.reset_with_seed(self.seed);
+    }
+
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        *self = Xxh64::with_seed(seed);
+    }
+
+    // into_state checkpoints the stream. The caller keeps track of how many bytes were
+    // written, which `from_state` needs to resume.
+    pub fn into_state(self) -> Xxh64State {
+        Xxh64State {
+            seed: self.seed,
+            accs: [self.acc1, self.acc2, self.acc3, self.acc4],
+            buffer: self.buffer.0,
+        }
+    }
+
+    // from_state resumes a stream checkpointed with `into_state` after `input_len` bytes.
+    pub fn from_state(state: Xxh64State, input_len: u64) -> Xxh64 {
+        Xxh64 {
+            seed: state.seed,
+            acc1: state.accs[0],
+            acc2: state.accs[1],
+            acc3: state.accs[2],
+            acc4: state.accs[3],
+            buffer: Align64(state.buffer),
+            // Whole stripes are always consumed, so only the remainder is buffered.
+            buffer_len: (input_len % STRIPE_LEN_32 as u64) as usize,
+            input_len,
+            skip_avalanche: false,
         }
     }
 
+    // with_config creates a digest with the given options. Digests without the avalanche
+    // produce different values from `finish`; the option is not part of `into_state` or
+    // `save_state`.
+    pub fn with_config(config: Xxh64Config) -> Xxh64 {
+        Xxh64 {
+            skip_avalanche: !config.avalanche,
+            ..Xxh64::with_seed(config.seed)
+        }
+    }
+
+    // save_state serializes the whole stream, including the input length, so it can be
+    // resumed with `load_state`, possibly on another machine. The layout is fixed and
+    // little-endian: seed, accumulators 1 to 4, the 32-byte stripe buffer with unused
+    // bytes zeroed, then the input length.
+    pub fn save_state(&self) -> [u8; XXH64_SAVED_STATE_LEN] {
+        let mut out = [0; XXH64_SAVED_STATE_LEN];
+        let words = [self.seed, self.acc1, self.acc2, self.acc3, self.acc4];
+        for (chunk, word) in out[..40].chunks_exact_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out[40..40 + self.buffer_len].copy_from_slice(&self.buffer.0[..self.buffer_len]);
+        out[72..].copy_from_slice(&self.input_len.to_le_bytes());
+        out
+    }
+
+    // load_state resumes a stream saved with `save_state`.
+    pub fn load_state(bytes: &[u8; XXH64_SAVED_STATE_LEN]) -> Xxh64 {
+        let mut accs = [0; 4];
+        for (acc, i) in accs.iter_mut().zip(1..) {
+            *acc = read_lane(bytes, i);
+        }
+        let mut buffer = [0; STRIPE_LEN_32];
+        buffer.copy_from_slice(&bytes[40..72]);
+        let state = Xxh64State {
+            seed: read_lane(bytes, 0),
+            accs,
+            buffer,
+        };
+        Xxh64::from_state(state, read_lane(bytes, 9))
+    }
+
     pub fn write(&mut self, bytes: &[u8]) {
-        self.input_len += bytes.len();
+        // The length is taken modulo 2^64, as in the reference implementation.
+        self.input_len = self.input_len.wrapping_add(bytes.len() as u64);
 
+        // Writes that stay within the current stripe, such as the many small writes of a
+        // derived `Hash`, only append to the buffer. A dedicated `write_u8` path measured
+        // slower than this one, see `derive_hash_benchmark`.
         if bytes.len() + self.buffer_len < STRIPE_LEN_32 {
             self.buffer.0[self.buffer_len..bytes.len() + self.buffer_len].copy_from_slice(bytes);
             self.buffer_len += bytes.len();
         } else {
-            // Need to consume extra bytes.
+            // Need to consume extra bytes. The outer check guarantees `bytes` can fill the
+            // rest of the buffer, so the split below is in bounds.
+            debug_assert!(bytes.len() >= STRIPE_LEN_32 - self.buffer_len);
             let mut accs = (self.acc1, self.acc2, self.acc3, self.acc4);
-            self.buffer.0[self.buffer_len..]
-                .copy_from_slice(&bytes[..STRIPE_LEN_32 - self.buffer_len]);
-            accs = Xxh64::process_stripe(accs, self.buffer.0);
-            let mut bytes_consumed = 32 - self.buffer_len;
-            let mut new_buffer_len = bytes.len() + self.buffer_len - 32;
-            while new_buffer_len >= 32 {
-                accs = Xxh64::process_stripe(
-                    accs,
-                    bytes[bytes_consumed..bytes_consumed + 32]
-                        .try_into()
-                        .expect("incorrect length"),
-                );
-                bytes_consumed += 32;
-                new_buffer_len -= 32;
+            let (head, rest) = bytes.split_at(STRIPE_LEN_32 - self.buffer_len);
+            self.buffer.0[self.buffer_len..].copy_from_slice(head);
+            accs = Xxh64::process_stripe(accs, &self.buffer.0);
+            let (whole, remainder) = rest.split_at(rest.len() - rest.len() % STRIPE_LEN_32);
+            for stripe in stripes(whole) {
+                accs = Xxh64::process_stripe(accs, stripe);
             }
             self.acc1 = accs.0;
             self.acc2 = accs.1;
             self.acc3 = accs.2;
             self.acc4 = accs.3;
-            self.buffer_len = new_buffer_len;
-            if new_buffer_len > 0 {
-                self.buffer.0[..new_buffer_len].copy_from_slice(&bytes[bytes_consumed..]);
-            }
+            self.buffer_len = remainder.len();
+            self.buffer.0[..remainder.len()].copy_from_slice(remainder);
+        }
+    }
+
+    pub fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+    }
+
+    // write_u64 is equivalent to `write(&v.to_le_bytes())`, with a fixed-size copy
+    // when the value fits in the buffer.
+    #[inline]
+    pub fn write_u64(&mut self, v: u64) {
+        if self.buffer_len + 8 < STRIPE_LEN_32 {
+            self.buffer.0[self.buffer_len..self.buffer_len + 8].copy_from_slice(&v.to_le_bytes());
+            self.buffer_len += 8;
+            self.input_len = self.input_len.wrapping_add(8);
+        } else {
+            self.write(&v.to_le_bytes());
+        }
+    }
+
+    // write_u32 is equivalent to `write(&v.to_le_bytes())`, with a fixed-size copy
+    // when the value fits in the buffer.
+    #[inline]
+    pub fn write_u32(&mut self, v: u32) {
+        if self.buffer_len + 4 < STRIPE_LEN_32 {
+            self.buffer.0[self.buffer_len..self.buffer_len + 4].copy_from_slice(&v.to_le_bytes());
+            self.buffer_len += 4;
+            self.input_len = self.input_len.wrapping_add(4);
+        } else {
+            self.write(&v.to_le_bytes());
         }
     }
 
+    // finish_hex returns `finish` as 16 lowercase hex digits, keeping leading zeros.
+    #[cfg(feature = "std")]
+    pub fn finish_hex(&self) -> String {
+        format!("{:016x}", self.finish())
+    }
+
+    // finish returns the digest of everything written so far. It does not modify the
+    // state, so it can be called repeatedly and writing may continue afterwards.
     pub fn finish(&self) -> u64 {
-        let mut slice = &self.buffer.0[..self.buffer_len];
+        let slice = &self.buffer.0[..self.buffer_len];
+        if self.input_len <= 16 && !self.skip_avalanche {
+            // Short inputs, such as `HashMap` keys, are still entirely in the buffer.
+            return xxh64_short(slice, self.seed);
+        }
         let mut acc;
-        if self.input_len >= STRIPE_LEN_32 {
+        if self.input_len >= STRIPE_LEN_32 as u64 {
             acc = self
                 .acc1
                 .rotate_left(1)
@@ -175,67 +635,71 @@ impl Xxh64 {
         } else {
             // Special case: input is less than 32 bytes.
             // The algorithm then proceeds directly to step 4.
-            acc = self.seed + PRIME64_5;
-        }
-        // Step 4. Add input length
-        acc = acc.wrapping_add(self.input_len as u64);
-        // Step 5. Consume remaining input
-        while slice.len() >= 8 {
-            let lane = u64::from_le_bytes(slice[0..8].try_into().expect("incorrect length"));
-            acc ^= round(0u64, lane);
-            acc = (acc.rotate_left(27)).wrapping_mul(PRIME64_1);
-            acc = acc.wrapping_add(PRIME64_4);
-            slice = &slice[8..slice.len()]
-        }
-        if slice.len() >= 4 {
-            let lane = u32::from_le_bytes(slice[0..4].try_into().expect("incorrect length")) as u64;
-            acc ^= lane.wrapping_mul(PRIME64_1);
-            acc = acc.rotate_left(23).wrapping_mul(PRIME64_2);
-            acc = acc.wrapping_add(PRIME64_3);
-            slice = &slice[4..slice.len()]
-        }
-        while slice.len() >= 1 {
-            let lane = slice[0] as u64;
-            acc ^= lane.wrapping_mul(PRIME64_5);
-            acc = acc.rotate_left(11).wrapping_mul(PRIME64_1);
-            slice = &slice[1..slice.len()]
-        }
-        // Step 6. Final mix (avalanche)
-        acc ^= acc >> 33;
-        acc = acc.wrapping_mul(PRIME64_2);
-        acc ^= acc >> 29;
-        acc = acc.wrapping_mul(PRIME64_3);
-        acc ^= acc >> 32;
-        acc
+            acc = self.seed.wrapping_add(PRIME64_5);
+        }
+        let acc = consume_tail(acc, self.input_len, slice);
+        if self.skip_avalanche {
+            acc
+        } else {
+            avalanche(acc)
+        }
+    }
+
+    // accumulators returns the four lane accumulators, for diagnostics and comparison with
+    // other implementations. Bytes of an incomplete stripe are still buffered and not
+    // reflected here.
+    pub fn accumulators(&self) -> (u64, u64, u64, u64) {
+        (self.acc1, self.acc2, self.acc3, self.acc4)
+    }
+
+    // seed returns the seed the digest was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // input_len returns the number of bytes written so far.
+    pub fn input_len(&self) -> u64 {
+        self.input_len
+    }
+
+    // bytes_written is the same as `input_len`, for callers checking how much data went
+    // into a digest.
+    pub fn bytes_written(&self) -> u64 {
+        self.input_len()
+    }
+
+    // finish_eq reports whether the digest of everything written so far is `expected`.
+    pub fn finish_eq(&self, expected: u64) -> bool {
+        self.finish() == expected
+    }
+
+    // Xxh64 used to implement `BuildHasher` itself, always building an unseeded digest.
+    // This keeps direct `digest.build_hasher()` calls compiling for one release. It is not
+    // a `BuildHasher`, so `HashMap`s must be built with `Xxh64BuildHasher`.
+    #[deprecated(since = "0.2.0", note = "use `Xxh64BuildHasher` to build hashers")]
+    pub fn build_hasher(&self) -> Xxh64 {
+        Xxh64::with_seed(0)
     }
 
+    // process_stripe takes the stripe as an array so the lane reads need no bounds checks.
+    // Streaming throughput was the same as with a slice, within noise.
     #[inline(always)]
     fn process_stripe(
         mut accs: (u64, u64, u64, u64),
-        slice: [u8; STRIPE_LEN_32],
+        stripe: &[u8; STRIPE_LEN_32],
     ) -> (u64, u64, u64, u64) {
         // Step 2. Process stripes
         // Each lane read its associated 64-bit value using little-endian convention.
-        accs.0 = round(
-            accs.0,
-            u64::from_le_bytes(slice[0..8].try_into().expect("incorrect length")),
-        );
-        accs.1 = round(
-            accs.1,
-            u64::from_le_bytes(slice[8..16].try_into().expect("incorrect length")),
-        );
-        accs.2 = round(
-            accs.2,
-            u64::from_le_bytes(slice[16..24].try_into().expect("incorrect length")),
-        );
-        accs.3 = round(
-            accs.3,
-            u64::from_le_bytes(slice[24..32].try_into().expect("incorrect length")),
-        );
+        accs.0 = round(accs.0, read_lane(stripe, 0));
+        accs.1 = round(accs.1, read_lane(stripe, 1));
+        accs.2 = round(accs.2, read_lane(stripe, 2));
+        accs.3 = round(accs.3, read_lane(stripe, 3));
         accs
     }
 }
 
+// Integers are hashed as little-endian bytes, so a derived `Hash` gives the same digest on
+// every host. The signed `write_i*` methods forward to these.
 impl Hasher for Xxh64 {
     fn finish(&self) -> u64 {
         self.finish()
@@ -244,76 +708,1657 @@ impl Hasher for Xxh64 {
     fn write(&mut self, bytes: &[u8]) {
         self.write(bytes);
     }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u32(i);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write_u64(i);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write(&i.to_le_bytes());
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for Xxh64 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Xxh64::write(self, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Xxh64BuildHasher builds `Xxh64` digests with a configured seed, e.g. for `HashMap`.
+#[derive(Clone, Copy, Default)]
+pub struct Xxh64BuildHasher {
+    seed: u64,
+}
+
+impl Xxh64BuildHasher {
+    pub fn with_seed(seed: u64) -> Xxh64BuildHasher {
+        Xxh64BuildHasher { seed }
+    }
+
+    // hash_one hashes a single value with a fresh digest. It behaves like
+    // `BuildHasher::hash_one`, which older toolchains do not provide.
+    #[allow(clippy::manual_hash_one)]
+    pub fn hash_one<T: Hash>(&self, x: T) -> u64 {
+        let mut hasher = self.build_hasher();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
-impl BuildHasher for Xxh64 {
+impl BuildHasher for Xxh64BuildHasher {
     type Hasher = Xxh64;
 
     #[inline(always)]
     fn build_hasher(&self) -> Self::Hasher {
-        Xxh64::with_seed(0)
+        Xxh64::with_seed(self.seed)
     }
 }
 
-impl Default for Xxh64 {
-    fn default() -> Self {
-        Xxh64 {
-            seed: 0,
-            acc1: PRIME64_1.wrapping_add(PRIME64_2),
-            acc2: PRIME64_2,
-            acc3: 0,
-            acc4: (0 as u64).wrapping_sub(PRIME64_1),
-            buffer: Align64([0; STRIPE_LEN_32]),
-            buffer_len: 0,
-            input_len: 0,
+// Xxh64RandomState is a drop-in replacement for std's `RandomState`: each instance draws
+// its own seed from the OS random number generator, and every map using it resists hash
+// flooding. `HashMap::<K, V, Xxh64RandomState>::default()` works as with `RandomState`.
+// Creating one panics if the OS generator fails.
+#[cfg(feature = "getrandom")]
+#[derive(Clone, Copy, Debug)]
+pub struct Xxh64RandomState {
+    seed: u64,
+}
+
+#[cfg(feature = "getrandom")]
+impl Xxh64RandomState {
+    pub fn new() -> Xxh64RandomState {
+        Xxh64RandomState {
+            seed: random_seed(),
         }
     }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
 }
 
-#[inline(always)]
-fn round(mut acc_n: u64, lan_n: u64) -> u64 {
-    acc_n = acc_n.wrapping_add(lan_n.wrapping_mul(PRIME64_2));
-    acc_n = acc_n.rotate_left(31);
-    acc_n = acc_n.wrapping_mul(PRIME64_1);
-    acc_n
+#[cfg(feature = "getrandom")]
+impl Default for Xxh64RandomState {
+    fn default() -> Self {
+        Xxh64RandomState::new()
+    }
 }
 
-#[inline(always)]
-fn merge_accumulator(mut acc: u64, acc_n: u64) -> u64 {
-    acc ^= round(0u64, acc_n);
-    acc = acc.wrapping_mul(PRIME64_1);
-    acc = acc.wrapping_add(PRIME64_4);
-    acc
+#[cfg(feature = "getrandom")]
+impl BuildHasher for Xxh64RandomState {
+    type Hasher = Xxh64;
+
+    #[inline(always)]
+    fn build_hasher(&self) -> Self::Hasher {
+        Xxh64::with_seed(self.seed)
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+// Draws a seed from the OS random number generator, panicking if it fails.
+#[cfg(feature = "getrandom")]
+fn random_seed() -> u64 {
+    let mut seed = [0; 8];
+    if let Err(err) = getrandom::getrandom(&mut seed) {
+        panic!("cannot get a random seed: {}", err);
+    }
+    u64::from_le_bytes(seed)
+}
 
-    use super::*;
+// Xxh64Builder collects the options for XXH64 hashing, so new options can be added
+// without more function variants. Options not set keep their defaults, e.g. seed 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Xxh64Builder {
+    seed: u64,
+}
 
-    #[test]
-    fn test_xxh64() {
-        assert_eq!(17241709254077376921, xxh64_slice(b"", 0));
-        assert_eq!(13237225503670494420, xxh64_slice(b"1", 0));
-        assert_eq!(3804218074556952421, xxh64_slice(b"01234", 0));
-        assert_eq!(4566581271137380327, xxh64_slice(b"0123456789", 0));
-        assert_eq!(3244596498076163532, xxh64_slice(b"01234567890123456789", 0));
-        assert_eq!(
-            14587097675127171377,
-            xxh64_slice(b"0123456789012345678901234567890123456789", 0)
-        );
-        assert_eq!(
-            6256723559432292107,
-            xxh64_slice(
-                b"01234567890123456789012345678901234567890123456789012345678901234567890123456789",
-                0
-            )
-        );
+impl Xxh64Builder {
+    pub fn new() -> Xxh64Builder {
+        Xxh64Builder::default()
+    }
 
-        let s = Xxh64::default();
-        let mut map = HashMap::with_capacity_and_hasher(10, s);
-        map.insert("qwer", 1);
+    pub fn seed(mut self, seed: u64) -> Xxh64Builder {
+        self.seed = seed;
+        self
+    }
+
+    // build returns a streaming digest with the configured options.
+    pub fn build(&self) -> Xxh64 {
+        Xxh64::with_seed(self.seed)
+    }
+
+    // hash hashes `bytes` in one shot with the configured options.
+    pub fn hash(&self, bytes: &[u8]) -> u64 {
+        xxh64_slice(bytes, self.seed)
+    }
+}
+
+// StrictXxh64 is an `Xxh64` for code that treats `finish` as terminal. Writing after
+// `finish` is valid for xxHash and simply continues the stream, but with this wrapper it
+// fails a debug assertion, which catches hashers that are reused by mistake. Release
+// builds behave exactly like `Xxh64`.
+#[derive(Clone, Debug, Default)]
+pub struct StrictXxh64 {
+    inner: Xxh64,
+    // `Hasher::finish` takes `&self`, hence the `Cell`. That makes StrictXxh64 deliberately
+    // `!Sync`; it is still `Send`, and hashers are not shared between threads anyway.
+    finished: Cell<bool>,
+}
+
+impl StrictXxh64 {
+    pub fn with_seed(seed: u64) -> StrictXxh64 {
+        StrictXxh64 {
+            inner: Xxh64::with_seed(seed),
+            finished: Cell::new(false),
+        }
+    }
+
+    // into_inner returns the wrapped digest, which accepts writes after `finish` again.
+    pub fn into_inner(self) -> Xxh64 {
+        self.inner
+    }
+
+    fn check_unfinished(&self) {
+        debug_assert!(!self.finished.get(), "write to StrictXxh64 after finish");
+    }
+}
+
+impl Hasher for StrictXxh64 {
+    fn finish(&self) -> u64 {
+        self.finished.set(true);
+        self.inner.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.check_unfinished();
+        self.inner.write(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.check_unfinished();
+        Hasher::write_u16(&mut self.inner, i);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.check_unfinished();
+        self.inner.write_u32(i);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.check_unfinished();
+        self.inner.write_u64(i);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.check_unfinished();
+        Hasher::write_u128(&mut self.inner, i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.check_unfinished();
+        Hasher::write_usize(&mut self.inner, i);
+    }
+}
+
+// Only the buffered bytes of the current stripe take part in comparisons: the rest of the
+// buffer holds leftovers that depend on how the input was split into writes.
+impl PartialEq for Xxh64 {
+    fn eq(&self, other: &Xxh64) -> bool {
+        self.seed == other.seed
+            && (self.acc1, self.acc2, self.acc3, self.acc4)
+                == (other.acc1, other.acc2, other.acc3, other.acc4)
+            && self.input_len == other.input_len
+            && self.skip_avalanche == other.skip_avalanche
+            && self.buffer.0[..self.buffer_len] == other.buffer.0[..other.buffer_len]
+    }
+}
+
+impl Eq for Xxh64 {}
+
+impl fmt::Debug for Xxh64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Xxh64")
+            .field("seed", &format_args!("{:#018x}", self.seed))
+            .field(
+                "accs",
+                &format_args!(
+                    "[{:#018x}, {:#018x}, {:#018x}, {:#018x}]",
+                    self.acc1, self.acc2, self.acc3, self.acc4
+                ),
+            )
+            .field("buffer_len", &self.buffer_len)
+            .field("input_len", &self.input_len)
+            .field("skip_avalanche", &self.skip_avalanche)
+            .finish()
+    }
+}
+
+impl XxHasher for Xxh64 {
+    type Output = u64;
+
+    fn with_seed(seed: u64) -> Xxh64 {
+        Xxh64::with_seed(seed)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+
+    fn digest(&self) -> u64 {
+        self.finish()
+    }
+}
+
+impl Default for Xxh64 {
+    fn default() -> Self {
+        Xxh64 {
+            seed: 0,
+            acc1: PRIME64_1.wrapping_add(PRIME64_2),
+            acc2: PRIME64_2,
+            acc3: 0,
+            acc4: 0u64.wrapping_sub(PRIME64_1),
+            buffer: Align64([0; STRIPE_LEN_32]),
+            buffer_len: 0,
+            input_len: 0,
+            skip_avalanche: false,
+        }
+    }
+}
+
+// Processes every complete stripe in `slice` with the best available backend.
+#[inline(always)]
+fn process_stripes(accs: (u64, u64, u64, u64), slice: &[u8]) -> (u64, u64, u64, u64) {
+    #[cfg(feature = "unstable-simd")]
+    {
+        simd::backend().process_stripes(accs, slice)
+    }
+    #[cfg(not(feature = "unstable-simd"))]
+    {
+        process_stripes_scalar(accs, slice)
+    }
+}
+
+fn process_stripes_scalar(accs: (u64, u64, u64, u64), slice: &[u8]) -> (u64, u64, u64, u64) {
+    process_stripes_unrolled::<2>(accs, slice)
+}
+
+// Processes `N` stripes per loop iteration, then any remaining stripes one at a time.
+// Each lane still consumes its stripes in order, so the result matches the plain loop.
+#[inline(always)]
+fn process_stripes_unrolled<const N: usize>(
+    mut accs: (u64, u64, u64, u64),
+    slice: &[u8],
+) -> (u64, u64, u64, u64) {
+    let mut blocks = slice.chunks_exact(N * STRIPE_LEN_32);
+    for (i, block) in (&mut blocks).enumerate() {
+        prefetch(slice, i * N * STRIPE_LEN_32 + PREFETCH_DISTANCE);
+        for stripe in stripes(block) {
+            accs = Xxh64::process_stripe(accs, stripe);
+        }
+    }
+    for stripe in stripes(blocks.remainder()) {
+        accs = Xxh64::process_stripe(accs, stripe);
+    }
+    accs
+}
+
+// How far ahead of the current stripe `prefetch` reaches, in bytes.
+const PREFETCH_DISTANCE: usize = 8 * STRIPE_LEN_32;
+
+// Hints the CPU to start loading `slice[offset..]` into the cache. Offsets past the end
+// are skipped; the hint never changes the result.
+#[cfg(all(feature = "prefetch", target_arch = "x86_64"))]
+#[inline(always)]
+fn prefetch(slice: &[u8], offset: usize) {
+    use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+    if let Some(ahead) = slice.get(offset) {
+        // SAFETY: SSE is part of the x86_64 baseline, and the pointer is in bounds.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(ahead as *const u8 as *const i8) };
+    }
+}
+
+#[cfg(not(all(feature = "prefetch", target_arch = "x86_64")))]
+#[inline(always)]
+fn prefetch(_slice: &[u8], _offset: usize) {}
+
+// xxh64_backend_name reports which stripe-processing backend `xxh64_slice` uses:
+// "avx2", "neon", "simd128" or "scalar".
+pub fn xxh64_backend_name() -> &'static str {
+    #[cfg(feature = "unstable-simd")]
+    {
+        simd::backend().name
+    }
+    #[cfg(not(feature = "unstable-simd"))]
+    {
+        "scalar"
+    }
+}
+
+// stripes splits `slice` into whole stripes, leaving out any trailing bytes like
+// `chunks_exact`. The conversion to arrays cannot fail and compiles away.
+#[inline(always)]
+fn stripes(slice: &[u8]) -> impl Iterator<Item = &[u8; STRIPE_LEN_32]> {
+    slice
+        .chunks_exact(STRIPE_LEN_32)
+        .filter_map(|stripe| stripe.try_into().ok())
+}
+
+// Reads the n-th 64-bit lane of a stripe.
+#[inline(always)]
+fn read_lane(stripe: &[u8], n: usize) -> u64 {
+    let mut lane = [0u8; 8];
+    lane.copy_from_slice(&stripe[n * 8..n * 8 + 8]);
+    u64::from_le_bytes(lane)
+}
+
+// Steps 4 to 6, shared by the one-shot and streaming paths.
+// `tail` is the input left over after the last complete stripe.
+#[inline(always)]
+fn finalize(acc: u64, input_len: u64, tail: &[u8]) -> u64 {
+    // Step 6. Final mix (avalanche)
+    avalanche(consume_tail(acc, input_len, tail))
+}
+
+// Steps 4 and 5.
+#[inline(always)]
+fn consume_tail(mut acc: u64, input_len: u64, tail: &[u8]) -> u64 {
+    let len = tail.len();
+    let mut offset = 0;
+    // Step 4. Add input length
+    acc = acc.wrapping_add(input_len);
+    // Step 5. Consume remaining input
+    while len - offset >= 8 {
+        acc = consume_u64(acc, read_lane(&tail[offset..], 0));
+        offset += 8;
+    }
+    if len - offset >= 4 {
+        acc = consume_u32(acc, read_u32(&tail[offset..]));
+        offset += 4;
+    }
+    while offset < len {
+        acc = consume_u8(acc, tail[offset]);
+        offset += 1;
+    }
+    acc
+}
+
+// Steps 4 to 6 for inputs of at most 16 bytes, such as `HashMap` keys, with the tail loop
+// unrolled. Used by `xxh64_slice` and `Xxh64::finish`; the result is the same as `finalize`.
+#[inline(always)]
+fn xxh64_short(data: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let mut acc = seed.wrapping_add(PRIME64_5).wrapping_add(len as u64);
+    let mut offset = 0;
+    if len >= 8 {
+        acc = consume_u64(acc, read_lane(data, 0));
+        offset = 8;
+        if len == 16 {
+            return avalanche(consume_u64(acc, read_lane(data, 1)));
+        }
+    }
+    if len - offset >= 4 {
+        acc = consume_u32(acc, read_u32(&data[offset..]));
+        offset += 4;
+    }
+    // At most three bytes are left.
+    if offset < len {
+        acc = consume_u8(acc, data[offset]);
+        if offset + 1 < len {
+            acc = consume_u8(acc, data[offset + 1]);
+            if offset + 2 < len {
+                acc = consume_u8(acc, data[offset + 2]);
+            }
+        }
+    }
+    avalanche(acc)
+}
+
+#[inline(always)]
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut lane = [0u8; 4];
+    lane.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(lane)
+}
+
+#[inline(always)]
+fn consume_u64(acc: u64, lane: u64) -> u64 {
+    (acc ^ round(0, lane))
+        .rotate_left(27)
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(PRIME64_4)
+}
+
+#[inline(always)]
+fn consume_u32(acc: u64, lane: u32) -> u64 {
+    (acc ^ (lane as u64).wrapping_mul(PRIME64_1))
+        .rotate_left(23)
+        .wrapping_mul(PRIME64_2)
+        .wrapping_add(PRIME64_3)
+}
+
+#[inline(always)]
+fn consume_u8(acc: u64, byte: u8) -> u64 {
+    (acc ^ (byte as u64).wrapping_mul(PRIME64_5))
+        .rotate_left(11)
+        .wrapping_mul(PRIME64_1)
+}
+
+#[inline(always)]
+fn avalanche(mut acc: u64) -> u64 {
+    acc ^= acc >> 33;
+    acc = acc.wrapping_mul(PRIME64_2);
+    acc ^= acc >> 29;
+    acc = acc.wrapping_mul(PRIME64_3);
+    acc ^= acc >> 32;
+    acc
+}
+
+#[inline(always)]
+const fn round(mut acc_n: u64, lan_n: u64) -> u64 {
+    acc_n = acc_n.wrapping_add(lan_n.wrapping_mul(PRIME64_2));
+    acc_n = acc_n.rotate_left(31);
+    acc_n = acc_n.wrapping_mul(PRIME64_1);
+    acc_n
+}
+
+#[inline(always)]
+const fn merge_accumulator(mut acc: u64, acc_n: u64) -> u64 {
+    acc ^= round(0u64, acc_n);
+    acc = acc.wrapping_mul(PRIME64_1);
+    acc = acc.wrapping_add(PRIME64_4);
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_xxh64() {
+        assert_eq!(17241709254077376921, xxh64_slice(b"", 0));
+        assert_eq!(13237225503670494420, xxh64_slice(b"1", 0));
+        assert_eq!(3804218074556952421, xxh64_slice(b"01234", 0));
+        assert_eq!(4566581271137380327, xxh64_slice(b"0123456789", 0));
+        assert_eq!(3244596498076163532, xxh64_slice(b"01234567890123456789", 0));
+        assert_eq!(
+            14587097675127171377,
+            xxh64_slice(b"0123456789012345678901234567890123456789", 0)
+        );
+        assert_eq!(
+            6256723559432292107,
+            xxh64_slice(
+                b"01234567890123456789012345678901234567890123456789012345678901234567890123456789",
+                0
+            )
+        );
+
+        assert_eq!(xxh64_slice(b"01234", 0), xxh64_str("01234", 0));
+        let owned = String::from("0123456789");
+        assert_eq!(xxh64_slice(b"0123456789", 0), xxh64_str(&owned, 0));
+
+        let s = Xxh64BuildHasher::default();
+        let mut map = HashMap::with_capacity_and_hasher(10, s);
+        map.insert("qwer", 1);
+    }
+
+    #[test]
+    fn test_xxh64_max_seed() {
+        // Seed initialization must wrap instead of overflowing in debug builds.
+        assert_eq!(125878816811915416, xxh64_slice(b"hello", u64::MAX));
+
+        let mut digest = Xxh64::with_seed(u64::MAX);
+        digest.write(b"hello");
+        assert_eq!(125878816811915416, digest.finish());
+
+        let long = [7u8; 100];
+        let mut digest = Xxh64::with_seed(u64::MAX);
+        digest.write(&long);
+        assert_eq!(xxh64_slice(&long, u64::MAX), digest.finish());
+    }
+
+    #[test]
+    fn test_xxh64_generic() {
+        use std::borrow::Cow;
+
+        let expected = xxh64_slice(b"hello", 10);
+        assert_eq!(expected, xxh64("hello", 10));
+        assert_eq!(expected, xxh64(String::from("hello"), 10));
+        let owned = String::from("hello");
+        assert_eq!(expected, xxh64(&owned, 10));
+        assert_eq!(expected, xxh64(b"hello", 10));
+        assert_eq!(expected, xxh64(*b"hello", 10));
+        assert_eq!(expected, xxh64(&b"hello"[..], 10));
+        let bytes = owned.clone().into_bytes();
+        assert_eq!(expected, xxh64(&bytes, 10));
+        assert_eq!(expected, xxh64(bytes, 10));
+        assert_eq!(expected, xxh64(Cow::Borrowed(&b"hello"[..]), 10));
+        assert_eq!(expected, xxh64(Cow::<[u8]>::Owned(b"hello".to_vec()), 10));
+        assert_eq!(expected, xxh64_str("hello", 10));
+        assert_eq!(xxh64_slice(b"", 0), xxh64("", 0));
+    }
+
+    #[test]
+    fn test_xxh64_u64_slice() {
+        let values: Vec<u64> = (0..100).map(|i| PRIME64_1.wrapping_mul(i)).collect();
+        for len in 0..=values.len() {
+            let bytes: Vec<u8> = values[..len]
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            let expected = xxh64_slice(&bytes, 10);
+            assert_eq!(expected, xxh64_u64_slice(&values[..len], 10));
+            // The other endianness's path, which must agree.
+            assert_eq!(expected, xxh64_u64_slice_swapped(&values[..len], 10));
+        }
+        assert_eq!(
+            xxh64_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0], 0),
+            xxh64_u64_slice(&[1, 2], 0)
+        );
+    }
+
+    #[test]
+    fn test_xxh64_framed() {
+        assert_ne!(
+            xxh64_framed(&[b"ab", b"c"], 0),
+            xxh64_framed(&[b"a", b"bc"], 0)
+        );
+        assert_eq!(
+            xxh64_slices(&[b"ab", b"c"], 0),
+            xxh64_slices(&[b"a", b"bc"], 0)
+        );
+        assert_ne!(xxh64_framed(&[b""], 0), xxh64_framed(&[], 0));
+        assert_ne!(xxh64_framed(&[b"", b"a"], 0), xxh64_framed(&[b"a", b""], 0));
+
+        let mut framed = Vec::new();
+        for field in [&b"ab"[..], b"", b"cde"].iter() {
+            framed.extend_from_slice(&(field.len() as u64).to_le_bytes());
+            framed.extend_from_slice(field);
+        }
+        assert_eq!(
+            xxh64_slice(&framed, 7),
+            xxh64_framed(&[b"ab", b"", b"cde"], 7)
+        );
+    }
+
+    #[test]
+    fn test_xxh64_slices() {
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 37 + 11) as u8).collect();
+        let (a, rest) = data.split_at(5);
+        let (b, c) = rest.split_at(60);
+        assert_eq!(
+            xxh64_slice(&[a, b, c].concat(), 0),
+            xxh64_slices(&[a, b, c], 0)
+        );
+        assert_eq!(xxh64_slice(&data, 10), xxh64_slices(&[a, &[], b, c], 10));
+        assert_eq!(xxh64_slice(b"", 10), xxh64_slices(&[], 10));
+    }
+
+    #[test]
+    fn test_xxh64_unordered() {
+        let items: Vec<u64> = (0..100).map(|i| PRIME64_1.wrapping_mul(i)).collect();
+        let expected = xxh64_unordered(items.iter().copied(), 10);
+        assert_eq!(expected, xxh64_unordered(items.iter().rev().copied(), 10));
+
+        let set: std::collections::HashSet<u64> = items.iter().copied().collect();
+        assert_eq!(expected, xxh64_unordered(set, 10));
+
+        assert_ne!(expected, xxh64_unordered(items.iter().copied(), 11));
+        assert_ne!(expected, xxh64_unordered(items[1..].iter().copied(), 10));
+        // Duplicates count, unlike with an XOR combination.
+        assert_ne!(
+            xxh64_unordered(vec![1, 2], 0),
+            xxh64_unordered(vec![1, 2, 2, 2], 0)
+        );
+        assert_ne!(xxh64_unordered(vec![], 0), xxh64_unordered(vec![5, 5], 0));
+    }
+
+    #[test]
+    fn test_xxh64_const() {
+        const HASH: u64 = xxh64_const(b"hello", 0);
+        assert_eq!(xxh64_slice(b"hello", 0), HASH);
+
+        let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        for len in 0..=data.len() {
+            assert_eq!(xxh64_slice(&data[..len], 10), xxh64_const(&data[..len], 10));
+        }
+    }
+
+    #[test]
+    fn test_xxh64_collides() {
+        // Fails to compile if the pair collides.
+        const _: () = assert!(!xxh64_collides(b"foo", b"bar", 0));
+        const _: () = assert!(xxh64_collides(b"foo", b"foo", 10));
+
+        assert!(!xxh64_collides(b"foo", b"bar", 10));
+        assert!(!xxh64_collides(b"", b"\0", 0));
+        assert_eq!(
+            xxh64_slice(b"foo", 7) == xxh64_slice(b"bar", 7),
+            xxh64_collides(b"foo", b"bar", 7)
+        );
+    }
+
+    #[test]
+    fn test_xxh64_canonical() {
+        let hash = xxh64_slice(b"", 0);
+        assert_eq!(
+            [0xef, 0x46, 0xdb, 0x37, 0x51, 0xd8, 0xe9, 0x99],
+            xxh64_canonical(hash)
+        );
+        assert_eq!(hash, xxh64_from_canonical(&xxh64_canonical(hash)));
+
+        let hash = xxh64_slice(b"0123456789", 0);
+        assert_eq!(hash, xxh64_from_canonical(&xxh64_canonical(hash)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_xxh64_footer() {
+        let payload = b"header\0records\0".to_vec();
+        let mut data = payload.clone();
+        append_xxh64_footer(&mut data, 10);
+        assert_eq!(payload.len() + 8, data.len());
+        assert_eq!(
+            xxh64_canonical(xxh64_slice(&payload, 10)),
+            data[payload.len()..]
+        );
+        assert_eq!(Some(&payload[..]), verify_and_strip_xxh64_footer(&data, 10));
+        assert_eq!(None, verify_and_strip_xxh64_footer(&data, 11));
+
+        let mut empty = Vec::new();
+        append_xxh64_footer(&mut empty, 0);
+        assert_eq!(Some(&[][..]), verify_and_strip_xxh64_footer(&empty, 0));
+
+        // Corrupting any byte, of the payload or of the footer, is detected.
+        for i in 0..data.len() {
+            let mut corrupted = data.clone();
+            corrupted[i] ^= 0x40;
+            assert_eq!(None, verify_and_strip_xxh64_footer(&corrupted, 10), "{}", i);
+        }
+        assert_eq!(None, verify_and_strip_xxh64_footer(&data[1..], 10));
+    }
+
+    #[test]
+    fn test_xxh64_footer_too_short() {
+        for len in 0..8 {
+            assert_eq!(None, verify_and_strip_xxh64_footer(&[0; 8][..len], 0));
+        }
+        // A footer alone is valid if it is the hash of the empty payload.
+        let footer = xxh64_canonical(xxh64_slice(b"", 0));
+        assert_eq!(Some(&[][..]), verify_and_strip_xxh64_footer(&footer, 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_xxh64_hex() {
+        let data = b"0123456789".repeat(10);
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&data);
+        assert_eq!(
+            format!("{:016x}", xxh64_slice(&data, 10)),
+            digest.finish_hex()
+        );
+        assert_eq!(digest.finish_hex(), xxh64_hex(&data, 10));
+        assert_eq!("26c7827d889f6da3", xxh64_hex(b"hello", 0));
+
+        // Find an input whose hash has a leading zero byte.
+        let key = (0..10000u32)
+            .map(u32::to_le_bytes)
+            .find(|key| xxh64_slice(key, 0) >> 56 == 0)
+            .unwrap();
+        let hex = xxh64_hex(&key, 0);
+        assert_eq!(16, hex.len());
+        assert!(hex.starts_with("00"), "{}", hex);
+        assert_eq!(Ok(xxh64_slice(&key, 0)), u64::from_str_radix(&hex, 16));
+    }
+
+    #[test]
+    fn test_xxh64_to_f64() {
+        assert_eq!(0.0, xxh64_to_f64(0));
+        assert_eq!(0.0, xxh64_to_f64((1 << 11) - 1));
+        assert!(xxh64_to_f64(u64::MAX) < 1.0);
+        assert_eq!(1.0 - f64::EPSILON / 2.0, xxh64_to_f64(u64::MAX));
+        assert_eq!(0.5, xxh64_to_f64(1 << 63));
+
+        assert_eq!(0.15148177685534636, xxh64_to_unit_interval(b"hello", 0));
+        for i in 0..10000u64 {
+            let x = xxh64_to_unit_interval(&i.to_le_bytes(), 10);
+            assert!((0.0..1.0).contains(&x), "{} -> {}", i, x);
+        }
+    }
+
+    #[test]
+    fn test_xxh64_verify_ct() {
+        let hash = xxh64_slice(b"hello world", 0);
+        assert!(xxh64_verify_ct(hash, hash));
+        assert!(xxh64_verify_ct(0, 0));
+        assert!(xxh64_verify_ct(u64::MAX, u64::MAX));
+        assert!(!xxh64_verify_ct(hash, xxh64_slice(b"hello world", 1)));
+        assert!(!xxh64_verify_ct(0, u64::MAX));
+        for bit in 0..64 {
+            assert!(!xxh64_verify_ct(hash, hash ^ (1 << bit)), "bit {}", bit);
+            assert!(!xxh64_verify_ct(0, 1 << bit), "bit {}", bit);
+        }
+    }
+
+    #[test]
+    fn test_xxh64_bucket() {
+        // Reference values from the algorithm in the paper.
+        let buckets: Vec<u32> = (0..8)
+            .map(|i| xxh64_bucket(format!("user-{}", i).as_bytes(), 0, 10))
+            .collect();
+        assert_eq!(vec![6, 6, 5, 8, 5, 8, 0, 7], buckets);
+        assert_eq!(309, xxh64_bucket(b"hello", 0, 1000));
+        assert_eq!(0, xxh64_bucket(b"hello", 0, 1));
+
+        let keys = 10000u64;
+        for &n in &[1, 2, 9, 10, 100] {
+            let mut moved = 0;
+            for i in 0..keys {
+                let key = i.to_le_bytes();
+                let before = xxh64_bucket(&key, 10, n);
+                let after = xxh64_bucket(&key, 10, n + 1);
+                assert!(before < n && after <= n);
+                if before != after {
+                    assert_eq!(n, after, "key {} moved between old buckets", i);
+                    moved += 1;
+                }
+            }
+            let expected = keys / (n as u64 + 1);
+            assert!(
+                moved > expected * 8 / 10 && moved < expected * 12 / 10,
+                "{} -> {} moved {} keys",
+                n,
+                n + 1,
+                moved
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "num_buckets must not be 0")]
+    fn test_xxh64_bucket_zero() {
+        xxh64_bucket(b"hello", 0, 0);
+    }
+
+    #[test]
+    fn test_send_sync() {
+        // Hashers are moved between threads, e.g. by work-stealing pools; this stops
+        // compiling if a field such as an `Rc` or a raw pointer takes that away.
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Xxh64>();
+        assert_send_sync::<Xxh64State>();
+        assert_send_sync::<Xxh64BuildHasher>();
+        assert_send_sync::<Xxh32>();
+        assert_send_sync::<Xxh32BuildHasher>();
+        assert_send_sync::<Xxh3_64>();
+        assert_send_sync::<Xxh128>();
+        assert_send_sync::<WindowHasher<64>>();
+        assert_send_sync::<XxhPhf<4, 17>>();
+        #[cfg(feature = "std")]
+        {
+            assert_send_sync::<VerifyingWriter<Vec<u8>>>();
+            assert_send_sync::<HashingWriter<Vec<u8>>>();
+        }
+        #[cfg(feature = "digest")]
+        assert_send_sync::<Xxh64Digest>();
+
+        // StrictXxh64 is `!Sync` by design, see the `finished` field.
+        fn assert_send<T: Send>() {}
+        assert_send::<StrictXxh64>();
+    }
+
+    #[test]
+    fn test_xxh64_clone() {
+        let prefix = b"0123456789012345678901234567890123456789";
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&prefix[..]);
+
+        let mut forked = digest.clone();
+        digest.write(b"abc");
+        forked.write(b"0123456789");
+
+        assert_eq!(
+            xxh64_slice(&[&prefix[..], b"abc"].concat(), 10),
+            digest.finish()
+        );
+        assert_eq!(
+            xxh64_slice(&[&prefix[..], b"0123456789"].concat(), 10),
+            forked.finish()
+        );
+    }
+
+    #[test]
+    fn test_xxh64_reset() {
+        let data = b"0123456789012345678901234567890123456789";
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(b"01234567890123456789012345678901234567890123456789");
+
+        digest.reset();
+        digest.write(&data[..]);
+        assert_eq!(xxh64_slice(&data[..], 10), digest.finish());
+
+        digest.reset_with_seed(20);
+        assert_eq!(Xxh64::with_seed(20), digest);
+        digest.write(&data[..]);
+        let mut fresh = Xxh64::with_seed(20);
+        fresh.write(&data[..]);
+        assert_eq!(fresh.finish(), digest.finish());
+        assert_eq!(fresh, digest);
+    }
+
+    #[test]
+    fn test_xxh64_write_splits() {
+        // Every split of every length up to three stripes into two and three writes,
+        // covering writes that exactly fill the buffer and ones that overflow it by one.
+        let data: Vec<u8> = (0..96u32).map(|i| (i * 37 + 11) as u8).collect();
+        for len in 0..=data.len() {
+            let input = &data[..len];
+            let expected = xxh64_slice(input, 10);
+            for i in 0..=len {
+                let mut digest = Xxh64::with_seed(10);
+                digest.write(&input[..i]);
+                digest.write(&input[i..]);
+                assert_eq!(expected, digest.finish(), "len {} split {}", len, i);
+
+                for j in i..=len {
+                    let mut digest = Xxh64::with_seed(10);
+                    digest.write(&input[..i]);
+                    digest.write(&input[i..j]);
+                    digest.write(&input[j..]);
+                    assert_eq!(expected, digest.finish(), "len {} splits {} {}", len, i, j);
+                }
+            }
+        }
+
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&data[..32]);
+        digest.write(&data[32..33]);
+        assert_eq!(xxh64_slice(&data[..33], 10), digest.finish());
+    }
+
+    #[test]
+    fn test_xxh64_streaming_chunk_sizes() {
+        let data: Vec<u8> = (0..10 * 1024u32).map(|i| (i * 97 + 13) as u8).collect();
+        let expected = xxh64_slice(&data, 10);
+        for chunk in [1, 3, 7, 31, 32, 33, 64, 100, 1000, 4096, 10 * 1024].iter() {
+            let mut digest = Xxh64::with_seed(10);
+            for part in data.chunks(*chunk) {
+                digest.write(part);
+            }
+            assert_eq!(expected, digest.finish(), "chunk {}", chunk);
+        }
+
+        // Chunk sizes that change on every write.
+        let mut digest = Xxh64::with_seed(10);
+        let mut rest = &data[..];
+        for size in (0..).map(|i| (i * 7) % 70) {
+            if rest.is_empty() {
+                break;
+            }
+            let (part, tail) = rest.split_at(size.min(rest.len()));
+            digest.write(part);
+            rest = tail;
+        }
+        assert_eq!(expected, digest.finish());
+    }
+
+    #[test]
+    fn test_xxh64_write_exact_fill() {
+        // Writes where `bytes.len() + buffer_len == 32` fill the buffer exactly, leaving it
+        // empty after the stripe is consumed.
+        let data: Vec<u8> = (0..64u32).map(|i| (i * 37 + 11) as u8).collect();
+        for buffered in 0..STRIPE_LEN_32 {
+            let mut digest = Xxh64::with_seed(10);
+            digest.write(&data[..buffered]);
+            digest.write(&data[buffered..STRIPE_LEN_32]);
+            assert_eq!(0, digest.buffer_len, "buffered {}", buffered);
+            assert_eq!(STRIPE_LEN_32 as u64, digest.input_len());
+            assert_eq!(xxh64_slice(&data[..STRIPE_LEN_32], 10), digest.finish());
+
+            digest.write(&data[STRIPE_LEN_32..]);
+            assert_eq!(0, digest.buffer_len, "buffered {}", buffered);
+            assert_eq!(xxh64_slice(&data, 10), digest.finish());
+        }
+    }
+
+    #[test]
+    fn test_xxh64_prefetch() {
+        // The streaming path never prefetches, so it checks the one-shot loop for inputs
+        // shorter than, near and past the prefetch distance.
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 31 + 7) as u8).collect();
+        for len in (0..=data.len()).step_by(13) {
+            let mut digest = Xxh64::with_seed(10);
+            digest.write(&data[..len]);
+            assert_eq!(
+                digest.finish(),
+                xxh64_slice(&data[..len], 10),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_stripes_unrolled() {
+        let data: Vec<u8> = (0..512u32).map(|i| (i * 37 + 11) as u8).collect();
+        let init = Xxh64::with_seed(10).accumulators();
+        for len in 0..=data.len() {
+            let slice = &data[..len - len % STRIPE_LEN_32];
+            let mut expected = init;
+            for stripe in stripes(slice) {
+                expected = Xxh64::process_stripe(expected, stripe);
+            }
+            assert_eq!(expected, process_stripes_unrolled::<1>(init, slice));
+            assert_eq!(expected, process_stripes_unrolled::<2>(init, slice));
+            assert_eq!(expected, process_stripes_unrolled::<4>(init, slice));
+
+            let mut digest = Xxh64::with_seed(10);
+            digest.write(&data[..len]);
+            assert_eq!(
+                digest.finish(),
+                xxh64_slice(&data[..len], 10),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_xxh64_accumulators() {
+        let mut digest = Xxh64::with_seed(0);
+        assert_eq!(
+            (
+                PRIME64_1.wrapping_add(PRIME64_2),
+                PRIME64_2,
+                0,
+                0u64.wrapping_sub(PRIME64_1)
+            ),
+            digest.accumulators()
+        );
+
+        let stripe: Vec<u8> = (0..32).collect();
+        digest.write(&stripe[..31]);
+        assert_eq!(Xxh64::with_seed(0).accumulators(), digest.accumulators());
+        assert_eq!(31, digest.input_len());
+
+        // One round per lane, computed by hand from the spec.
+        digest.write(&stripe[31..]);
+        assert_eq!(
+            (
+                0xf4b53b57406b5e99,
+                0xc3d34b511fc32561,
+                0xcb26c007c8a1553f,
+                0x9a44d001a7f91c07
+            ),
+            digest.accumulators()
+        );
+        assert_eq!(32, digest.input_len());
+    }
+
+    #[test]
+    fn test_xxh64_seed() {
+        assert_eq!(123, Xxh64::with_seed(123).seed());
+        let digest: Xxh64 = Default::default();
+        assert_eq!(0, digest.seed());
+
+        let mut digest = Xxh64::with_seed(u64::MAX);
+        digest.write(&[1; 100]);
+        assert_eq!(u64::MAX, digest.seed());
+        assert_eq!(Xxh64::with_seed(u64::MAX), Xxh64::with_seed(digest.seed()));
+    }
+
+    #[test]
+    fn test_xxh64_finish_eq() {
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(b"hello");
+        assert!(digest.finish_eq(xxh64_slice(b"hello", 10)));
+        assert!(!digest.finish_eq(xxh64_slice(b"hello", 0)));
+    }
+
+    #[test]
+    fn test_xxh64_eq_debug() {
+        let data: Vec<u8> = (0..72).collect();
+        let mut whole = Xxh64::with_seed(10);
+        whole.write(&data);
+        let mut split = Xxh64::with_seed(10);
+        for chunk in data.chunks(32) {
+            split.write(chunk);
+        }
+        // Equal despite different leftovers in the buffer.
+        assert_eq!(whole, split);
+        assert_eq!(whole, whole.clone());
+        split.write(b"x");
+        assert_ne!(whole, split);
+        assert_ne!(Xxh64::with_seed(1), Xxh64::with_seed(2));
+
+        assert_eq!(
+            "Xxh64 { seed: 0x0000000000000000, accs: [0x60ea27eeadc0b5d6, \
+             0xc2b2ae3d27d4eb4f, 0x0000000000000000, 0x61c8864e7a143579], \
+             buffer_len: 0, input_len: 0, skip_avalanche: false }",
+            format!("{:?}", Xxh64::default())
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_xxh64_io_write() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let mut digest = Xxh64::with_seed(10);
+        let copied = io::copy(&mut io::Cursor::new(&data), &mut digest).unwrap();
+        assert_eq!(data.len() as u64, copied);
+        assert_eq!(xxh64_slice(&data, 10), digest.finish());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_xxh64_serde() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&data[..500]);
+
+        let state = serde_json::to_string(&digest).unwrap();
+        let mut restored: Xxh64 = serde_json::from_str(&state).unwrap();
+        restored.write(&data[500..]);
+        assert_eq!(xxh64_slice(&data, 10), restored.finish());
+    }
+
+    #[test]
+    fn test_xxh64_write_int() {
+        let mut fast = Xxh64::with_seed(10);
+        let mut bytes = Xxh64::with_seed(10);
+        for i in 0..100u64 {
+            let v = i.wrapping_mul(PRIME64_1);
+            if i % 3 == 0 {
+                fast.write_u32(v as u32);
+                bytes.write(&(v as u32).to_le_bytes());
+            } else {
+                fast.write_u64(v);
+                bytes.write(&v.to_le_bytes());
+            }
+            assert_eq!(bytes.finish(), fast.finish());
+        }
+
+        let mut hasher = Xxh64::with_seed(10);
+        Hasher::write_u64(&mut hasher, 42);
+        Hasher::write_u32(&mut hasher, 7);
+        let mut expected = Xxh64::with_seed(10);
+        expected.write(&42u64.to_le_bytes());
+        expected.write(&7u32.to_le_bytes());
+        assert_eq!(expected.finish(), Hasher::finish(&hasher));
+
+        // Every integer width is little-endian, whatever the host.
+        let mut hasher = Xxh64::with_seed(10);
+        let mut strict = StrictXxh64::with_seed(10);
+        for h in [&mut hasher as &mut dyn Hasher, &mut strict] {
+            h.write_u8(1);
+            h.write_u16(0x0203);
+            h.write_i16(-2);
+            h.write_u128(0x0405_0607_0809_0a0b_0c0d_0e0f_1011_1213);
+            h.write_i128(-3);
+            h.write_usize(0x1415);
+            h.write_isize(-4);
+        }
+        let mut expected = Xxh64::with_seed(10);
+        expected.write(&[1]);
+        expected.write(&0x0203u16.to_le_bytes());
+        expected.write(&(-2i16).to_le_bytes());
+        expected.write(&0x0405_0607_0809_0a0b_0c0d_0e0f_1011_1213u128.to_le_bytes());
+        expected.write(&(-3i128).to_le_bytes());
+        expected.write(&0x1415usize.to_le_bytes());
+        expected.write(&(-4isize).to_le_bytes());
+        assert_eq!(expected.finish(), hasher.finish());
+        assert_eq!(expected.finish(), strict.finish());
+    }
+
+    #[test]
+    fn test_xxh64_small_writes() {
+        // Mixed small writes, as made by a derived `Hash`, crossing stripe boundaries at
+        // every offset.
+        let mut hasher = Xxh64::with_seed(10);
+        let mut bytes = Vec::new();
+        for i in 0..200u32 {
+            match i % 4 {
+                0 => {
+                    Hasher::write_u8(&mut hasher, i as u8);
+                    bytes.push(i as u8);
+                }
+                1 => {
+                    Hasher::write_u32(&mut hasher, i * 7919);
+                    bytes.extend_from_slice(&(i * 7919).to_le_bytes());
+                }
+                2 => {
+                    Hasher::write_u8(&mut hasher, 0xff);
+                    Hasher::write_u8(&mut hasher, 0);
+                    bytes.extend_from_slice(&[0xff, 0]);
+                }
+                _ => {
+                    Hasher::write_u64(&mut hasher, u64::from(i) << 40);
+                    bytes.extend_from_slice(&(u64::from(i) << 40).to_le_bytes());
+                }
+            }
+            assert_eq!(xxh64_slice(&bytes, 10), Hasher::finish(&hasher), "{}", i);
+        }
+    }
+
+    #[test]
+    fn test_xxh64_finish_idempotent() {
+        let data: Vec<u8> = (0..100).map(|i| i as u8).collect();
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&data[..40]);
+        let first = digest.finish();
+        assert_eq!(first, digest.finish());
+        assert_eq!(xxh64_slice(&data[..40], 10), first);
+
+        digest.write(&data[40..]);
+        assert_eq!(xxh64_slice(&data, 10), digest.finish());
+        assert_eq!(digest.finish(), digest.finish());
+    }
+
+    #[test]
+    fn test_xxh64_hash_iter() {
+        let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        for len in 0..=data.len() {
+            assert_eq!(
+                xxh64_slice(&data[..len], 0),
+                Xxh64::hash_iter(data[..len].iter().copied(), 0)
+            );
+        }
+        assert_eq!(
+            xxh64_slice(&data, 10),
+            Xxh64::hash_iter((0..200).map(|i| i as u8), 10)
+        );
+    }
+
+    #[test]
+    fn test_xxh64_build_hasher() {
+        fn buckets(builder: &Xxh64BuildHasher) -> Vec<u64> {
+            (0..100u64)
+                .map(|key| {
+                    let mut hasher = builder.build_hasher();
+                    hasher.write_u64(key);
+                    Hasher::finish(&hasher) % 16
+                })
+                .collect()
+        }
+
+        let seeded = Xxh64BuildHasher::with_seed(42);
+        let mut hasher = seeded.build_hasher();
+        hasher.write(b"qwer");
+        assert_eq!(xxh64_slice(b"qwer", 42), hasher.finish());
+        assert_ne!(buckets(&seeded), buckets(&Xxh64BuildHasher::default()));
+
+        let mut map = HashMap::with_hasher(seeded);
+        map.insert("qwer", 1);
+        assert_eq!(Some(&1), map.get("qwer"));
+    }
+
+    #[test]
+    fn test_xx_hasher() {
+        fn check<H: XxHasher>(data: &[u8], seed: u64, expected: H::Output)
+        where
+            H::Output: PartialEq + core::fmt::Debug,
+        {
+            let mut hasher = H::with_seed(seed);
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(expected, hasher.digest());
+        }
+
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + 3) as u8).collect();
+        for &len in &[0, 5, 31, 100, 1000] {
+            let input = &data[..len];
+            check::<Xxh64>(input, 10, xxh64_slice(input, 10));
+            check::<Xxh32>(input, 10, xxh32_slice(input, 10));
+            check::<Xxh3_64>(input, 10, xxh3_64_slice(input, 10));
+            check::<Xxh128>(input, 10, xxh3_128_slice(input, 10));
+        }
+        // XXH32 only takes the low 32 bits of the seed.
+        check::<Xxh32>(&data, 1 << 32 | 10, xxh32_slice(&data, 10));
+    }
+
+    #[test]
+    fn test_xxh64_builder() {
+        assert_eq!(xxh64_slice(b"x", 7), Xxh64Builder::new().seed(7).hash(b"x"));
+        assert_eq!(xxh64_slice(b"x", 0), Xxh64Builder::new().hash(b"x"));
+        assert_eq!(
+            Xxh64Builder::new().seed(3).seed(7),
+            Xxh64Builder::new().seed(7)
+        );
+
+        let builder = Xxh64Builder::new().seed(7);
+        let mut digest = builder.build();
+        digest.write(b"hello");
+        assert_eq!(builder.hash(b"hello"), digest.finish());
+        assert_eq!(Xxh64::with_seed(7), builder.build());
+    }
+
+    #[test]
+    fn test_xxh64_hash_one() {
+        let builder = Xxh64BuildHasher::with_seed(42);
+        let value = (1u32, String::from("qwer"), vec![7u8; 3]);
+        assert_eq!(builder.hash_one(&value), builder.hash_one(&value));
+        assert_eq!(builder.hash_one("qwer"), builder.hash_one("qwer"));
+        assert_ne!(builder.hash_one("qwer"), builder.hash_one("qwes"));
+
+        assert_eq!(
+            BuildHasher::hash_one(&builder, &value),
+            builder.hash_one(&value)
+        );
+        assert_ne!(
+            builder.hash_one("qwer"),
+            Xxh64BuildHasher::default().hash_one("qwer")
+        );
+    }
+
+    #[test]
+    fn test_xxh64_state() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 13 + 5) as u8).collect();
+        for split in 0..=data.len() {
+            let mut digest = Xxh64::with_seed(10);
+            digest.write(&data[..split]);
+            let state = digest.into_state();
+
+            let mut resumed = Xxh64::from_state(state, split as u64);
+            assert_eq!(xxh64_slice(&data[..split], 10), resumed.finish());
+            resumed.write(&data[split..]);
+            assert_eq!(xxh64_slice(&data, 10), resumed.finish());
+            assert_eq!(state, Xxh64::from_state(state, split as u64).into_state());
+        }
+    }
+
+    #[test]
+    fn test_xxh64_save_state() {
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 13 + 5) as u8).collect();
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&data[..777]);
+        let saved = digest.save_state();
+        assert_eq!(XXH64_SAVED_STATE_LEN, saved.len());
+
+        let mut resumed = Xxh64::load_state(&saved);
+        assert_eq!(digest, resumed);
+        assert_eq!(777, resumed.input_len());
+        assert_eq!(10, resumed.seed());
+        resumed.write(&data[777..]);
+        assert_eq!(xxh64_slice(&data, 10), resumed.finish());
+
+        // Equal streams save identical bytes, however the input was split.
+        for split in 0..=100 {
+            let mut a = Xxh64::with_seed(10);
+            a.write(&data[..split]);
+            a.write(&data[split..100]);
+            let mut b = Xxh64::with_seed(10);
+            b.write(&data[..100]);
+            assert_eq!(b.save_state(), a.save_state());
+            assert_eq!(b, Xxh64::load_state(&a.save_state()));
+        }
+
+        // The layout is fixed.
+        let saved = Xxh64::with_seed(0x0102030405060708).save_state();
+        assert_eq!([8, 7, 6, 5, 4, 3, 2, 1], saved[..8]);
+        assert_eq!(
+            Xxh64::with_seed(0x0102030405060708).accumulators().0,
+            u64::from_le_bytes([
+                saved[8], saved[9], saved[10], saved[11], saved[12], saved[13], saved[14],
+                saved[15]
+            ])
+        );
+        assert_eq!([0; 40], saved[40..]);
+    }
+
+    #[test]
+    fn test_strict_xxh64() {
+        let mut strict = StrictXxh64::with_seed(7);
+        let mut plain = Xxh64::with_seed(7);
+        for mut hasher in [&mut strict as &mut dyn Hasher, &mut plain] {
+            hasher.write(b"hello");
+            hasher.write_u32(1);
+            hasher.write_u64(2);
+            "world".hash(&mut hasher);
+        }
+        assert_eq!(plain.finish(), strict.finish());
+        // finish may be called more than once.
+        assert_eq!(plain.finish(), strict.finish());
+        assert_eq!(plain, strict.into_inner());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "write to StrictXxh64 after finish")]
+    fn test_strict_xxh64_write_after_finish() {
+        let mut strict = StrictXxh64::default();
+        strict.write(b"hello");
+        let _ = strict.finish();
+        strict.write(b"world");
+    }
+
+    #[test]
+    fn test_xxh64_with_config() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut digest = Xxh64::with_config(Xxh64Config::default());
+        digest.write(&data);
+        assert_eq!(xxh64_slice(&data, 0), digest.finish());
+
+        let no_avalanche = |data: &[u8], seed| {
+            let mut digest = Xxh64::with_config(Xxh64Config {
+                seed,
+                avalanche: false,
+            });
+            let (head, tail) = data.split_at(data.len() / 3);
+            digest.write(head);
+            digest.write(tail);
+            digest.finish()
+        };
+        assert_eq!(PRIME64_5, no_avalanche(b"", 0));
+        assert_eq!(6010836485393404825, no_avalanche(b"hello world", 7));
+        assert_eq!(7111073982605952846, no_avalanche(&data, 0));
+        assert_eq!(13356120006033195279, no_avalanche(&data, 7));
+        assert_ne!(
+            Xxh64::with_config(Xxh64Config {
+                seed: 0,
+                avalanche: false,
+            }),
+            Xxh64::with_seed(0)
+        );
+    }
+
+    #[test]
+    fn test_xxh64_empty_streaming() {
+        let digest = Xxh64::with_seed(5);
+        assert_eq!(xxh64_slice(b"", 5), digest.finish());
+        assert_eq!(5467884547391421755, digest.finish());
+
+        let mut digest = Xxh64::default();
+        assert_eq!(xxh64_slice(b"", 0), digest.finish());
+        assert_eq!(17241709254077376921, digest.finish());
+        digest.write(&[]);
+        digest.write(b"");
+        assert_eq!(xxh64_slice(b"", 0), digest.finish());
+        assert_eq!(Xxh64::with_seed(0), digest);
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_xxh64_with_random_seed() {
+        let mut a = Xxh64::with_random_seed();
+        let mut b = Xxh64::with_random_seed();
+        // Equal seeds have a probability of 2^-64.
+        assert_ne!(a.seed(), b.seed());
+        a.write(b"hello world");
+        b.write(b"hello world");
+        assert_eq!(xxh64_slice(b"hello world", a.seed()), a.finish());
+        assert_eq!(xxh64_slice(b"hello world", b.seed()), b.finish());
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_xxh64_random_state() {
+        use std::collections::HashMap;
+
+        let mut map: HashMap<u64, String, Xxh64RandomState> = HashMap::default();
+        for i in 0..1000 {
+            map.insert(i, i.to_string());
+        }
+        assert_eq!(1000, map.len());
+        for i in 0..1000 {
+            assert_eq!(Some(&i.to_string()), map.get(&i));
+        }
+        assert_eq!(None, map.get(&1000));
+
+        let state = map.hasher();
+        let mut hasher = state.build_hasher();
+        hasher.write(b"key");
+        assert_eq!(xxh64_slice(b"key", state.seed()), hasher.finish());
+        assert_ne!(state.seed(), Xxh64RandomState::new().seed());
+    }
+
+    #[test]
+    fn test_xxh64_bytes_written() {
+        let mut digest = Xxh64::with_seed(0);
+        assert_eq!(0, digest.bytes_written());
+        digest.write(&[1; 10]);
+        digest.write(&[2; 20]);
+        digest.write(&[3; 30]);
+        assert_eq!(60, digest.bytes_written());
+        digest.write_u32(7);
+        digest.write_u64(7);
+        assert_eq!(72, digest.bytes_written());
+        assert_eq!(digest.input_len(), digest.bytes_written());
+
+        // The length wraps instead of overflowing, like the reference implementation.
+        let mut digest = Xxh64::from_state(digest.into_state(), u64::MAX - 4);
+        digest.write(&[4; 10]);
+        assert_eq!(5, digest.bytes_written());
+        digest.finish();
+    }
+
+    #[test]
+    fn test_xxh64_large_input_len() {
+        // A stream that has seen 4 GiB more than it really has, as a 32-bit target would
+        // after many writes. The digests come from the C implementation with its state's
+        // total_len raised by the same amount.
+        let data: Vec<u8> = (0..37u32).map(|i| (i * 13 + 5) as u8).collect();
+        let mut digest = Xxh64::with_seed(10);
+        digest.write(&data);
+        assert_eq!(5806577444935398954, digest.finish());
+
+        let mut digest = Xxh64::from_state(digest.into_state(), (1 << 32) + 37);
+        assert_eq!((1 << 32) + 37, digest.input_len());
+        assert_eq!(15798080552484699919, digest.finish());
+
+        let more: Vec<u8> = (0..100u32).map(|i| (i * 7 + 1) as u8).collect();
+        digest.write(&more);
+        assert_eq!((1 << 32) + 137, digest.input_len());
+        assert_eq!(11015955317115472447, digest.finish());
+    }
+
+    #[test]
+    fn test_xxh64_shorthands() {
+        assert_eq!(xxh64_slice(b"x", 0), hash(b"x"));
+        assert_eq!(xxh64_slice(b"", 0), hash(b""));
+
+        let mut digest = Xxh64::default();
+        digest.write_str("hello ");
+        digest.write_str("world");
+        assert_eq!(xxh64_str("hello world", 0), digest.finish());
+    }
+
+    #[test]
+    fn test_xxh64_tail() {
+        // `xxh64_const` keeps its own tail loop, so it serves as the reference here.
+        let data: Vec<u8> = (0..64).map(|i| (i * 37 + 11) as u8).collect();
+        for len in 0..=data.len() {
+            let mut digest = Xxh64::with_seed(10);
+            digest.write(&data[..len]);
+            assert_eq!(xxh64_const(&data[..len], 10), xxh64_slice(&data[..len], 10));
+            assert_eq!(xxh64_const(&data[..len], 10), digest.finish());
+        }
+    }
+
+    #[test]
+    fn test_xxh64_endianness() {
+        // The spec reads lanes as little-endian whatever the host byte order, so these
+        // reference digests must hold on big-endian targets too. CI runs them on one.
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(0x0807060504030201, read_lane(&bytes, 0));
+        assert_eq!(0x04030201, read_u32(&bytes));
+
+        // Stripes, then 8-byte, 4-byte and single-byte tail lanes.
+        let data: Vec<u8> = (0..111).collect();
+        assert_eq!(7380491470304042584, xxh64_slice(&data, 0));
+        assert_eq!(12339433490811553937, xxh64_slice(&data[..103], 0));
+        assert_eq!(12198270006012390445, xxh64_slice(&data[..15], 0));
+        assert_eq!(7380491470304042584, xxh64_const(&data, 0));
+        let mut digest = Xxh64::with_seed(0);
+        for chunk in data.chunks(7) {
+            digest.write(chunk);
+        }
+        assert_eq!(7380491470304042584, digest.finish());
+        assert_eq!(2822245453, xxh32_slice(&data, 0));
+        assert_eq!(12742801087891626303, xxh3_64_slice(&data, 0));
+
+        // Integer writes feed their little-endian bytes.
+        let mut digest = Xxh64::with_seed(0);
+        digest.write_u64(0x0102030405060708);
+        digest.write_u32(0x0a0b0c0d);
+        assert_eq!(6391229071365418579, digest.finish());
+    }
+
+    // Statistical checks that catch broken backends or mixing steps which still happen to
+    // pass the fixed vectors. The inputs are fixed, so the results are deterministic.
+    fn chi_squared(bucket_counts: &[u64], samples: u64) -> f64 {
+        let expected = samples as f64 / bucket_counts.len() as f64;
+        bucket_counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    fn test_xxh64_uniformity() {
+        // 1024 buckets from the top and the bottom bits: chi-squared has 1023 degrees of
+        // freedom, mean 1023 and standard deviation about 45.
+        let mut high = vec![0; 1024];
+        let mut low = vec![0; 1024];
+        let samples = 1_000_000u64;
+        for i in 0..samples {
+            let hash = xxh64_slice(&i.to_le_bytes(), 0);
+            high[(hash >> 54) as usize] += 1;
+            low[(hash & 1023) as usize] += 1;
+        }
+        assert!(chi_squared(&high, samples) < 1300.0);
+        assert!(chi_squared(&low, samples) < 1300.0);
+
+        // The same through the stripe loop, with the counter in the last lane.
+        let mut buckets = vec![0; 1024];
+        let mut input = [0x5a; 64];
+        let samples = 100_000u64;
+        for i in 0..samples {
+            input[56..].copy_from_slice(&i.to_le_bytes());
+            buckets[(xxh64_slice(&input, 0) >> 54) as usize] += 1;
+        }
+        assert!(chi_squared(&buckets, samples) < 1300.0);
+    }
+
+    #[test]
+    fn test_xxh64_avalanche() {
+        // Flipping any input bit should flip each output bit about half the time.
+        fn check(len: usize, inputs: u64) {
+            let mut flips = vec![[0u64; 64]; len * 8];
+            let mut input = vec![0u8; len];
+            for i in 0..inputs {
+                for (j, byte) in input.iter_mut().enumerate() {
+                    *byte = (PRIME64_1.wrapping_mul(i + 1) >> (j % 8 * 8)) as u8 ^ j as u8;
+                }
+                let hash = xxh64_slice(&input, 0);
+                for (bit, counts) in flips.iter_mut().enumerate() {
+                    input[bit / 8] ^= 1 << (bit % 8);
+                    let diff = hash ^ xxh64_slice(&input, 0);
+                    input[bit / 8] ^= 1 << (bit % 8);
+                    for (out, count) in counts.iter_mut().enumerate() {
+                        *count += diff >> out & 1;
+                    }
+                }
+            }
+            for (bit, counts) in flips.iter().enumerate() {
+                for (out, &count) in counts.iter().enumerate() {
+                    let p = count as f64 / inputs as f64;
+                    assert!(
+                        (0.43..0.57).contains(&p),
+                        "len {}: input bit {} flips output bit {} with p {}",
+                        len,
+                        bit,
+                        out,
+                        p
+                    );
+                }
+            }
+        }
+        check(8, 10_000);
+        check(64, 2_000);
+    }
+
+    #[test]
+    fn test_no_panic() {
+        // Every algorithm, one-shot and streaming, over every length up to several XXH3
+        // blocks, split at an awkward point.
+        let data: Vec<u8> = (0..2100u32).map(|i| (i * 131 + 17) as u8).collect();
+        for len in 0..=data.len() {
+            let input = &data[..len];
+            let split = len / 3;
+            let mut x64 = Xxh64::with_seed(1);
+            let mut x32 = Xxh32::with_seed(1);
+            let mut x3 = Xxh3_64::with_seed(1);
+            for part in &[&input[..split], &input[split..]] {
+                x64.write(part);
+                x32.write(part);
+                x3.write(part);
+            }
+            assert_eq!(xxh64_slice(input, 1), x64.finish());
+            assert_eq!(xxh32_slice(input, 1), x32.finish());
+            assert_eq!(xxh3_64_slice(input, 1), x3.finish());
+            xxh3_128_slice(input, 1);
+            xxh64_const(input, 1);
+        }
+    }
+
+    #[test]
+    fn test_xxh64_short() {
+        let data: Vec<u8> = (0..16).map(|i| (i * 53 + 7) as u8).collect();
+        for len in 0..=data.len() {
+            for &seed in &[0, 10, u64::MAX] {
+                let input = &data[..len];
+                let expected = finalize(seed.wrapping_add(PRIME64_5), len as u64, input);
+                assert_eq!(expected, xxh64_short(input, seed), "len {}", len);
+                assert_eq!(expected, xxh64_slice(input, seed), "len {}", len);
+                assert_eq!(expected, xxh64_const(input, seed), "len {}", len);
+                let mut digest = Xxh64::with_seed(seed);
+                digest.write(input);
+                assert_eq!(expected, digest.finish(), "len {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_xxh64_random_lengths() {
+        let mut state: u64 = 1;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 33
+        };
+        let data: Vec<u8> = (0..4096).map(|_| next() as u8).collect();
+        for _ in 0..500 {
+            let len = next() as usize % data.len();
+            let split = next() as usize % (len + 1);
+            let mut digest = Xxh64::with_seed(len as u64);
+            digest.write(&data[..split]);
+            digest.write(&data[split..len]);
+            let expected = xxh64_const(&data[..len], len as u64);
+            assert_eq!(expected, xxh64_slice(&data[..len], len as u64));
+            assert_eq!(expected, digest.finish());
+        }
     }
 
     #[test]
@@ -0,0 +1,44 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{xxh64_reader, xxh64_slice};
+
+// xxh64_mmap hashes a file by memory-mapping it rather than reading it through a buffer.
+//
+// The file must not be modified while it is being hashed, or the result is unspecified.
+pub fn xxh64_mmap<P: AsRef<Path>>(path: P, seed: u64) -> io::Result<u64> {
+    let file = File::open(path)?;
+    // Mapping an empty file fails or is undefined on some platforms.
+    if file.metadata()?.len() == 0 {
+        return xxh64_reader(file, seed);
+    }
+    // Safety: the map is read-only and dropped before returning. Concurrent modification
+    // of the file is documented above as unsupported.
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(xxh64_slice(&map, seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_xxh64_mmap() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7) as u8).collect();
+        let path = std::env::temp_dir().join(format!("xxh-mmap-{}", std::process::id()));
+        fs::write(&path, &data).unwrap();
+        let result = xxh64_mmap(&path, 10);
+        fs::write(&path, b"").unwrap();
+        let empty = xxh64_mmap(&path, 10);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(xxh64_slice(&data, 10), result.unwrap());
+        assert_eq!(xxh64_slice(b"", 10), empty.unwrap());
+        assert!(xxh64_mmap(&path, 10).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use std::thread;
+
+use crate::{xxh64_slice, Xxh64};
+
+// xxh64_slice_parallel splits `data` into `chunk`-sized pieces, hashes them with XXH64 on up
+// to `threads` threads, then folds the per-chunk digests with `xxh64_combine_chunks`.
+//
+// This is a tree-hash construction, not XXH64: the result depends on `chunk` and will not
+// match `xxh64_slice` of the same data. It does not depend on `threads`.
+pub fn xxh64_slice_parallel(data: &[u8], seed: u64, chunk: usize, threads: usize) -> u64 {
+    assert!(chunk > 0, "chunk size must be non-zero");
+    assert!(threads > 0, "thread count must be non-zero");
+
+    let chunks: Vec<&[u8]> = data.chunks(chunk).collect();
+    let mut digests = vec![0u64; chunks.len()];
+    let per_thread = chunks.len().div_ceil(threads);
+    if per_thread > 0 {
+        thread::scope(|s| {
+            for (inputs, outputs) in chunks
+                .chunks(per_thread)
+                .zip(digests.chunks_mut(per_thread))
+            {
+                s.spawn(move || {
+                    for (input, output) in inputs.iter().zip(outputs) {
+                        *output = xxh64_slice(input, seed);
+                    }
+                });
+            }
+        });
+    }
+    xxh64_combine_chunks(&digests, seed)
+}
+
+// xxh64_combine_chunks is the stable combination step of `xxh64_slice_parallel`:
+// the XXH64, with the same seed, of the per-chunk digests in order, each encoded
+// as 8 little-endian bytes.
+pub fn xxh64_combine_chunks(digests: &[u64], seed: u64) -> u64 {
+    let mut digest = Xxh64::with_seed(seed);
+    for d in digests {
+        digest.write(&d.to_le_bytes());
+    }
+    digest.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xxh64_slice_parallel() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 + i / 256) as u8).collect();
+        for &chunk in &[1000, 4096, 99_999, 100_000, 1_000_000] {
+            let digests: Vec<u64> = data.chunks(chunk).map(|c| xxh64_slice(c, 10)).collect();
+            let expected = xxh64_combine_chunks(&digests, 10);
+            for threads in 1..=8 {
+                assert_eq!(expected, xxh64_slice_parallel(&data, 10, chunk, threads));
+            }
+        }
+
+        assert_eq!(
+            xxh64_combine_chunks(&[], 10),
+            xxh64_slice_parallel(b"", 10, 1024, 4)
+        );
+    }
+}
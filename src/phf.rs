@@ -0,0 +1,170 @@
+use crate::{xxh64_const, xxh64_slice};
+
+// Seeds tried by `XxhPhf::new` before giving up.
+const MAX_SEEDS: u64 = 1 << 16;
+
+const EMPTY: u16 = u16::MAX;
+
+// XxhPhf is a perfect hash table over `N` string keys with `B` slots, usually built at
+// compile time with `xxh_phf!`. Construction searches for a seed under which every key
+// lands in its own slot, so a lookup is one hash and one string comparison.
+#[derive(Clone, Debug)]
+pub struct XxhPhf<const N: usize, const B: usize> {
+    seed: u64,
+    keys: [&'static str; N],
+    // Index into `keys` of the key in each slot, or `EMPTY`.
+    slots: [u16; B],
+}
+
+impl<const N: usize, const B: usize> XxhPhf<N, B> {
+    // new builds the table. It panics, at compile time when used in a constant, if the
+    // keys contain duplicates or no collision-free seed is found; more slots make a seed
+    // easier to find.
+    pub const fn new(keys: [&'static str; N]) -> XxhPhf<N, B> {
+        assert!(N < EMPTY as usize, "too many keys");
+        assert!(B > 0 && B >= N, "there must be at least one slot per key");
+        let mut i = 0;
+        while i < N {
+            let mut j = i + 1;
+            while j < N {
+                assert!(
+                    !bytes_eq(keys[i].as_bytes(), keys[j].as_bytes()),
+                    "duplicate key"
+                );
+                j += 1;
+            }
+            i += 1;
+        }
+
+        let mut seed = 0;
+        while seed < MAX_SEEDS {
+            if let Some(slots) = place(&keys, seed) {
+                return XxhPhf { seed, keys, slots };
+            }
+            seed += 1;
+        }
+        panic!("no collision-free seed found");
+    }
+
+    // get returns the index of `key` in the keys the table was built from.
+    pub fn get(&self, key: &str) -> Option<usize> {
+        let slot = self.slots[slot_of(xxh64_slice(key.as_bytes(), self.seed), B)] as usize;
+        match self.keys.get(slot) {
+            Some(candidate) if *candidate == key => Some(slot),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    // seed returns the seed that separates the keys.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn keys(&self) -> &[&'static str; N] {
+        &self.keys
+    }
+}
+
+// Assigns every key a slot under `seed`, or returns `None` on the first collision.
+const fn place<const N: usize, const B: usize>(
+    keys: &[&'static str; N],
+    seed: u64,
+) -> Option<[u16; B]> {
+    let mut slots = [EMPTY; B];
+    let mut i = 0;
+    while i < N {
+        let slot = slot_of(xxh64_const(keys[i].as_bytes(), seed), B);
+        if slots[slot] != EMPTY {
+            return None;
+        }
+        slots[slot] = i as u16;
+        i += 1;
+    }
+    Some(slots)
+}
+
+const fn slot_of(hash: u64, slots: usize) -> usize {
+    (hash % slots as u64) as usize
+}
+
+// `==` on slices is not usable in const fn.
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// xxh_phf! declares a static `XxhPhf` over the given keys, built at compile time with
+// N * N + 1 slots, which keeps the expected number of seeds tried small:
+//
+//     xxh_phf!(static KEYWORDS = ["fn", "let", "match"]);
+//     assert_eq!(Some(1), KEYWORDS.get("let"));
+#[macro_export]
+macro_rules! xxh_phf {
+    ($vis:vis static $name:ident = [$($key:expr),* $(,)?]) => {
+        $vis static $name: $crate::XxhPhf<
+            { <[&str]>::len(&[$($key),*]) },
+            { <[&str]>::len(&[$($key),*]) * <[&str]>::len(&[$($key),*]) + 1 },
+        > = $crate::XxhPhf::new([$($key),*]);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    xxh_phf!(static KEYWORDS = [
+        "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    ]);
+
+    xxh_phf!(static NONE = []);
+
+    #[test]
+    fn test_xxh_phf() {
+        assert_eq!(20, KEYWORDS.keys().len());
+        let mut slots = HashSet::new();
+        for (i, key) in KEYWORDS.keys().iter().enumerate() {
+            assert_eq!(Some(i), KEYWORDS.get(key), "{}", key);
+            assert!(KEYWORDS.contains(key));
+            let slot = slot_of(xxh64_slice(key.as_bytes(), KEYWORDS.seed()), 401);
+            assert!(slots.insert(slot), "{} shares a slot", key);
+        }
+
+        for key in &["", "As", "fn ", "while", "mutable", "m"] {
+            assert_eq!(None, KEYWORDS.get(key), "{}", key);
+        }
+        assert_eq!(None, NONE.get("fn"));
+    }
+
+    #[test]
+    fn test_xxh_phf_runtime() {
+        // `new` also works outside constants, with the same seed.
+        let phf = XxhPhf::<3, 3>::new(["a", "b", "c"]);
+        for (i, key) in ["a", "b", "c"].iter().enumerate() {
+            assert_eq!(Some(i), phf.get(key));
+        }
+        const PHF: XxhPhf<3, 3> = XxhPhf::new(["a", "b", "c"]);
+        assert_eq!(PHF.seed(), phf.seed());
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate key")]
+    fn test_xxh_phf_duplicate() {
+        XxhPhf::<2, 4>::new(["a", "a"]);
+    }
+}
@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::Xxh64;
+
+// Default buffer capacity used when hashing a reader.
+pub(crate) const READER_CAPACITY: usize = 64 * 1024;
+
+// Bounds on the buffer `xxh64_reader_sized` allocates for a size hint.
+const SIZED_CAPACITY_MIN: usize = 4 * 1024;
+const SIZED_CAPACITY_MAX: usize = 16 * 1024 * 1024;
+
+pub fn xxh64_reader<R: Read>(reader: R, seed: u64) -> io::Result<u64> {
+    xxh64_reader_with_capacity(reader, seed, READER_CAPACITY)
+}
+
+// xxh64_reader_with_capacity is `xxh64_reader` with a buffer of `capacity` bytes. A zero
+// capacity returns an `InvalidInput` error without reading.
+pub fn xxh64_reader_with_capacity<R: Read>(
+    reader: R,
+    seed: u64,
+    capacity: usize,
+) -> io::Result<u64> {
+    if capacity == 0 {
+        // An empty `BufReader` would look like end of input and hash nothing.
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "buffer capacity must not be zero",
+        ));
+    }
+    xxh64_bufread(&mut BufReader::with_capacity(capacity, reader), seed)
+}
+
+// xxh64_bufread hashes the rest of `reader`, straight out of its buffer with
+// `fill_buf`/`consume` rather than copying into one of our own. Prefer it to
+// `xxh64_reader` for sources that are already a `BufRead`.
+pub fn xxh64_bufread<R: BufRead + ?Sized>(reader: &mut R, seed: u64) -> io::Result<u64> {
+    let mut digest = Xxh64::with_seed(seed);
+    loop {
+        let length = {
+            let data = match reader.fill_buf() {
+                Ok(data) => data,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            digest.write(data);
+            data.len()
+        };
+        if length == 0 {
+            break;
+        }
+        reader.consume(length)
+    }
+    Ok(digest.finish())
+}
+
+// xxh64_reader_with_buf reads through the caller's `scratch` buffer instead of allocating
+// one. Any non-empty buffer gives the right result, but anything below one 32-byte stripe
+// defeats the stripe loop; a few KiB or more (`READER_CAPACITY` is 64 KiB) is sensible.
+// An empty buffer returns an `InvalidInput` error without reading.
+pub fn xxh64_reader_with_buf<R: Read>(
+    mut reader: R,
+    seed: u64,
+    scratch: &mut [u8],
+) -> io::Result<u64> {
+    if scratch.is_empty() {
+        // A zero-length read would look like end of input and hash nothing.
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "scratch buffer must not be empty",
+        ));
+    }
+    let mut digest = Xxh64::with_seed(seed);
+    loop {
+        let n = match reader.read(scratch) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        digest.write(&scratch[..n]);
+    }
+    Ok(digest.finish())
+}
+
+// xxh64_reader_sized hashes `reader` with a buffer sized to the expected input length,
+// e.g. from `Content-Length`, so inputs up to 16 MiB are read in a single call plus the
+// one that finds the end. The hint only affects performance: a wrong one still gives the
+// right digest. Without a hint it behaves like `xxh64_reader`.
+pub fn xxh64_reader_sized<R: Read>(
+    reader: R,
+    seed: u64,
+    size_hint: Option<usize>,
+) -> io::Result<u64> {
+    let capacity = size_hint.map_or(READER_CAPACITY, |size| {
+        size.clamp(SIZED_CAPACITY_MIN, SIZED_CAPACITY_MAX)
+    });
+    xxh64_reader_with_buf(reader, seed, &mut vec![0; capacity])
+}
+
+pub fn xxh64_file<P: AsRef<Path>>(path: P, seed: u64) -> io::Result<u64> {
+    xxh64_reader(File::open(path)?, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::xxh64_slice;
+
+    #[test]
+    fn test_xxh64_reader() {
+        let mut state: u64 = 1;
+        let data: Vec<u8> = (0..1024 * 1024)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect();
+
+        assert_eq!(
+            xxh64_slice(&data, 10),
+            xxh64_reader(Cursor::new(&data), 10).unwrap()
+        );
+        assert_eq!(
+            xxh64_slice(&data, 10),
+            xxh64_reader_with_capacity(Cursor::new(&data), 10, 100).unwrap()
+        );
+        assert_eq!(
+            xxh64_slice(b"", 0),
+            xxh64_reader(Cursor::new(b""), 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xxh64_bufread() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 29 + 5) as u8).collect();
+        for capacity in [1, 31, 32, 100, 8192].iter() {
+            let mut reader = BufReader::with_capacity(*capacity, Cursor::new(&data));
+            assert_eq!(
+                xxh64_slice(&data, 10),
+                xxh64_bufread(&mut reader, 10).unwrap()
+            );
+            // The reader is left at the end.
+            assert!(reader.fill_buf().unwrap().is_empty());
+        }
+
+        // Hashing starts from the reader's current position.
+        let mut reader = BufReader::new(Cursor::new(&data));
+        let mut head = [0; 10];
+        reader.read_exact(&mut head).unwrap();
+        assert_eq!(
+            xxh64_slice(&data[10..], 0),
+            xxh64_bufread(&mut reader, 0).unwrap()
+        );
+
+        // Slices are `BufRead` too.
+        assert_eq!(
+            xxh64_slice(&data, 0),
+            xxh64_bufread(&mut &data[..], 0).unwrap()
+        );
+        assert_eq!(
+            xxh64_slice(b"", 0),
+            xxh64_bufread(&mut io::empty(), 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xxh64_reader_with_buf() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 13 + 1) as u8).collect();
+        let mut scratch = [0u8; 8];
+        assert_eq!(
+            xxh64_slice(&data, 10),
+            xxh64_reader_with_buf(Cursor::new(&data), 10, &mut scratch).unwrap()
+        );
+        let mut scratch = vec![0u8; READER_CAPACITY];
+        assert_eq!(
+            xxh64_slice(&data, 10),
+            xxh64_reader_with_buf(Cursor::new(&data), 10, &mut scratch).unwrap()
+        );
+        assert_eq!(
+            xxh64_slice(b"", 10),
+            xxh64_reader_with_buf(Cursor::new(b""), 10, &mut scratch).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xxh64_reader_with_empty_buf() {
+        let err = xxh64_reader_with_buf(Cursor::new(b"x"), 0, &mut []).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn test_xxh64_reader_with_zero_capacity() {
+        let err = xxh64_reader_with_capacity(Cursor::new(b"x"), 0, 0).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!(
+            xxh64_slice(b"x", 0),
+            xxh64_reader_with_capacity(Cursor::new(b"x"), 0, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xxh64_reader_sized() {
+        // Counts the read calls made on the inner reader.
+        struct Counting<R> {
+            inner: R,
+            reads: usize,
+        }
+        impl<R: Read> Read for Counting<R> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.reads += 1;
+                self.inner.read(buf)
+            }
+        }
+
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 13 + 1) as u8).collect();
+        let expected = xxh64_slice(&data, 10);
+        for &hint in &[
+            Some(data.len()),
+            Some(0),
+            Some(10),
+            Some(data.len() - 1),
+            Some(1 << 30),
+            None,
+        ] {
+            let mut reader = Counting {
+                inner: Cursor::new(&data),
+                reads: 0,
+            };
+            assert_eq!(
+                expected,
+                xxh64_reader_sized(&mut reader, 10, hint).unwrap(),
+                "{:?}",
+                hint
+            );
+            if hint == Some(data.len()) {
+                assert_eq!(2, reader.reads);
+            }
+        }
+        assert_eq!(
+            xxh64_slice(b"", 10),
+            xxh64_reader_sized(Cursor::new(b""), 10, Some(0)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xxh64_file() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7) as u8).collect();
+        let path = std::env::temp_dir().join(format!("xxh-file-{}", std::process::id()));
+        fs::write(&path, &data).unwrap();
+        let result = xxh64_file(&path, 10);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(xxh64_slice(&data, 10), result.unwrap());
+
+        assert!(xxh64_file(&path, 10).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use digest::consts::U8;
+use digest::{FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset, Update};
+
+use crate::{xxh64_canonical, Xxh64};
+
+// Xxh64Digest adapts `Xxh64` to the RustCrypto `digest` traits.
+// The output is the 8-byte canonical (big-endian) form of the digest.
+#[derive(Clone, Default)]
+pub struct Xxh64Digest(Xxh64);
+
+impl Xxh64Digest {
+    pub fn with_seed(seed: u64) -> Xxh64Digest {
+        Xxh64Digest(Xxh64::with_seed(seed))
+    }
+}
+
+impl HashMarker for Xxh64Digest {}
+
+impl OutputSizeUser for Xxh64Digest {
+    type OutputSize = U8;
+}
+
+impl Update for Xxh64Digest {
+    fn update(&mut self, data: &[u8]) {
+        self.0.write(data);
+    }
+}
+
+impl FixedOutput for Xxh64Digest {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&xxh64_canonical(self.0.finish()));
+    }
+}
+
+impl FixedOutputReset for Xxh64Digest {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&xxh64_canonical(self.0.finish()));
+        self.0.reset();
+    }
+}
+
+impl Reset for Xxh64Digest {
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::Digest;
+
+    use super::*;
+    use crate::xxh64_slice;
+
+    #[test]
+    fn test_xxh64_digest_trait() {
+        assert_eq!(
+            xxh64_canonical(xxh64_slice(b"abc", 0)),
+            Xxh64Digest::digest(b"abc").as_slice()
+        );
+
+        let mut hasher = Xxh64Digest::with_seed(10);
+        Digest::update(&mut hasher, b"ab");
+        Digest::update(&mut hasher, b"c");
+        assert_eq!(
+            xxh64_canonical(xxh64_slice(b"abc", 10)),
+            hasher.finalize_reset().as_slice()
+        );
+        Digest::update(&mut hasher, b"abc");
+        assert_eq!(
+            xxh64_canonical(xxh64_slice(b"abc", 10)),
+            hasher.finalize().as_slice()
+        );
+    }
+}
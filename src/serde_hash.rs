@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+use crate::Xxh64;
+
+// xxh64_serde hashes any `Serialize` value, e.g. a config struct used as a cache key. The
+// value is serialized with bincode's default options, which are fixed-width and
+// little-endian, straight into the digest without an intermediate buffer. Values that
+// serialize the same hash the same, so renaming a field keeps the hash while reordering
+// fields or changing a type changes it.
+pub fn xxh64_serde<T: Serialize + ?Sized>(value: &T, seed: u64) -> Result<u64, bincode::Error> {
+    let mut digest = Xxh64::with_seed(seed);
+    bincode::serialize_into(&mut digest, value)?;
+    Ok(digest.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::xxh64_slice;
+
+    #[derive(Serialize, Clone)]
+    struct Config {
+        name: String,
+        retries: u32,
+        tags: Vec<String>,
+        limits: BTreeMap<String, u64>,
+        timeout: Option<f64>,
+    }
+
+    #[test]
+    fn test_xxh64_serde() {
+        let config = Config {
+            name: "cache".to_string(),
+            retries: 3,
+            tags: vec!["a".to_string(), "b".to_string()],
+            limits: vec![("memory".to_string(), 1 << 30)].into_iter().collect(),
+            timeout: Some(1.5),
+        };
+        let hash = xxh64_serde(&config, 0).unwrap();
+        assert_eq!(hash, xxh64_serde(&config.clone(), 0).unwrap());
+        assert_ne!(hash, xxh64_serde(&config, 1).unwrap());
+
+        let mut changed = config.clone();
+        changed.retries = 4;
+        assert_ne!(hash, xxh64_serde(&changed, 0).unwrap());
+        let mut changed = config.clone();
+        changed.tags.push("c".to_string());
+        assert_ne!(hash, xxh64_serde(&changed, 0).unwrap());
+        let mut changed = config;
+        changed.timeout = None;
+        assert_ne!(hash, xxh64_serde(&changed, 0).unwrap());
+
+        // A string is its length as a u64 followed by its bytes.
+        let mut encoded = 5u64.to_le_bytes().to_vec();
+        encoded.extend_from_slice(b"hello");
+        assert_eq!(xxh64_slice(&encoded, 7), xxh64_serde("hello", 7).unwrap());
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::OnceLock;
+
+use crate::process_stripes_scalar;
+
+// Unsafe because a backend may need CPU features that only `detect` checks for.
+type ProcessStripes = unsafe fn((u64, u64, u64, u64), &[u8]) -> (u64, u64, u64, u64);
+
+// Backend is a stripe-processing implementation, selected once per process. Only `detect`
+// creates backends, so `process_stripes` always runs on a CPU that supports it.
+pub(crate) struct Backend {
+    pub(crate) name: &'static str,
+    process_stripes: ProcessStripes,
+}
+
+impl Backend {
+    pub(crate) fn process_stripes(
+        &self,
+        accs: (u64, u64, u64, u64),
+        slice: &[u8],
+    ) -> (u64, u64, u64, u64) {
+        // SAFETY: `detect` only picks a backend after checking the CPU features it needs.
+        unsafe { (self.process_stripes)(accs, slice) }
+    }
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+pub(crate) fn backend() -> &'static Backend {
+    BACKEND.get_or_init(detect)
+}
+
+fn detect() -> Backend {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return Backend {
+                name: "avx2",
+                process_stripes: avx2::process_stripes,
+            };
+        }
+    }
+    // NEON is part of the aarch64 baseline, so there is nothing to detect.
+    #[cfg(all(
+        target_arch = "aarch64",
+        target_endian = "little",
+        target_feature = "neon"
+    ))]
+    {
+        return Backend {
+            name: "neon",
+            process_stripes: neon::process_stripes,
+        };
+    }
+    // WebAssembly cannot detect features at runtime: the SIMD loop is compiled in only when
+    // building with `-C target-feature=+simd128`.
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        return Backend {
+            name: "simd128",
+            process_stripes: wasm::process_stripes,
+        };
+    }
+    #[allow(unreachable_code)]
+    Backend {
+        name: "scalar",
+        process_stripes: process_stripes_scalar,
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod avx2 {
+    use core::arch::x86_64::*;
+
+    use crate::{PRIME64_1, PRIME64_2, STRIPE_LEN_32};
+
+    /// Processes every complete stripe in `slice`, with the four accumulators held in one
+    /// 256-bit register. Trailing bytes that do not fill a stripe are ignored.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn process_stripes(
+        accs: (u64, u64, u64, u64),
+        slice: &[u8],
+    ) -> (u64, u64, u64, u64) {
+        let mut acc = _mm256_set_epi64x(accs.3 as i64, accs.2 as i64, accs.1 as i64, accs.0 as i64);
+        let prime1 = _mm256_set1_epi64x(PRIME64_1 as i64);
+        let prime2 = _mm256_set1_epi64x(PRIME64_2 as i64);
+        for stripe in slice.chunks_exact(STRIPE_LEN_32) {
+            // x86_64 is little-endian, so an unaligned load reads the lanes as the spec requires.
+            let lanes = _mm256_loadu_si256(stripe.as_ptr() as *const __m256i);
+            acc = _mm256_add_epi64(acc, mul64(lanes, prime2));
+            acc = _mm256_or_si256(_mm256_slli_epi64(acc, 31), _mm256_srli_epi64(acc, 33));
+            acc = mul64(acc, prime1);
+        }
+        let mut out = [0u64; 4];
+        _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, acc);
+        (out[0], out[1], out[2], out[3])
+    }
+
+    // AVX2 has no 64-bit multiply, so build the low 64 bits of the product
+    // out of three 32x32->64 multiplies.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mul64(a: __m256i, b: __m256i) -> __m256i {
+        let a_hi = _mm256_srli_epi64(a, 32);
+        let b_hi = _mm256_srli_epi64(b, 32);
+        let lo_lo = _mm256_mul_epu32(a, b);
+        let cross = _mm256_add_epi64(_mm256_mul_epu32(a, b_hi), _mm256_mul_epu32(a_hi, b));
+        _mm256_add_epi64(lo_lo, _mm256_slli_epi64(cross, 32))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_avx2_process_stripes() {
+            if is_x86_feature_detected!("avx2") {
+                // SAFETY: AVX2 was just detected.
+                crate::simd::tests::check_process_stripes(|accs, slice| unsafe {
+                    process_stripes(accs, slice)
+                });
+            }
+        }
+    }
+}
+
+// Only compiled where NEON is enabled for the whole build, which makes the loop safe to
+// call.
+#[cfg(all(
+    target_arch = "aarch64",
+    target_endian = "little",
+    target_feature = "neon"
+))]
+pub(crate) mod neon {
+    use core::arch::aarch64::*;
+
+    use crate::{PRIME64_1, PRIME64_2, STRIPE_LEN_32};
+
+    // Processes every complete stripe in `slice`, with the four accumulators held in two
+    // 128-bit registers. Trailing bytes that do not fill a stripe are ignored.
+    pub(crate) fn process_stripes(
+        accs: (u64, u64, u64, u64),
+        slice: &[u8],
+    ) -> (u64, u64, u64, u64) {
+        // SAFETY: the module is only compiled with NEON enabled, and every load reads 16
+        // bytes within a stripe.
+        unsafe {
+            let mut acc12 = vld1q_u64([accs.0, accs.1].as_ptr());
+            let mut acc34 = vld1q_u64([accs.2, accs.3].as_ptr());
+            for stripe in slice.chunks_exact(STRIPE_LEN_32) {
+                // Byte loads have no alignment requirement, and the target is little-endian,
+                // so the lanes are read as the spec requires.
+                let lanes12 = vreinterpretq_u64_u8(vld1q_u8(stripe.as_ptr()));
+                let lanes34 = vreinterpretq_u64_u8(vld1q_u8(stripe.as_ptr().add(16)));
+                acc12 = round(acc12, lanes12);
+                acc34 = round(acc34, lanes34);
+            }
+            let mut out = [0u64; 4];
+            vst1q_u64(out.as_mut_ptr(), acc12);
+            vst1q_u64(out.as_mut_ptr().add(2), acc34);
+            (out[0], out[1], out[2], out[3])
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn round(acc: uint64x2_t, lanes: uint64x2_t) -> uint64x2_t {
+        let acc = vaddq_u64(acc, mul64(lanes, PRIME64_2));
+        let acc = vorrq_u64(vshlq_n_u64::<31>(acc), vshrq_n_u64::<33>(acc));
+        mul64(acc, PRIME64_1)
+    }
+
+    // NEON has no 64-bit multiply either, so build the low 64 bits of the product out of
+    // 32x32->64 multiplies, as in the AVX2 path.
+    #[inline(always)]
+    unsafe fn mul64(a: uint64x2_t, b: u64) -> uint64x2_t {
+        let a_lo = vmovn_u64(a);
+        let a_hi = vshrn_n_u64::<32>(a);
+        let b_lo = vdup_n_u32(b as u32);
+        let b_hi = vdup_n_u32((b >> 32) as u32);
+        let cross = vmlal_u32(vmull_u32(a_lo, b_hi), a_hi, b_lo);
+        vmlal_u32(vshlq_n_u64::<32>(cross), a_lo, b_lo)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_neon_process_stripes() {
+            crate::simd::tests::check_process_stripes(process_stripes);
+        }
+    }
+}
+
+// Only compiled where simd128 is enabled for the whole build, which makes the loop safe to
+// call.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub(crate) mod wasm {
+    use core::arch::wasm32::*;
+
+    use crate::{PRIME64_1, PRIME64_2, STRIPE_LEN_32};
+
+    // Processes every complete stripe in `slice`, with the four accumulators held in two
+    // 128-bit registers. Trailing bytes that do not fill a stripe are ignored.
+    pub(crate) fn process_stripes(
+        accs: (u64, u64, u64, u64),
+        slice: &[u8],
+    ) -> (u64, u64, u64, u64) {
+        let mut acc12 = u64x2(accs.0, accs.1);
+        let mut acc34 = u64x2(accs.2, accs.3);
+        for stripe in slice.chunks_exact(STRIPE_LEN_32) {
+            // SAFETY: both loads read 16 bytes within the stripe, and `v128_load` has no
+            // alignment requirement. WebAssembly is little-endian, so the lanes are read as
+            // the spec requires.
+            let (lanes12, lanes34) = unsafe {
+                (
+                    v128_load(stripe.as_ptr() as *const v128),
+                    v128_load(stripe.as_ptr().add(16) as *const v128),
+                )
+            };
+            acc12 = round(acc12, lanes12);
+            acc34 = round(acc34, lanes34);
+        }
+        (
+            u64x2_extract_lane::<0>(acc12),
+            u64x2_extract_lane::<1>(acc12),
+            u64x2_extract_lane::<0>(acc34),
+            u64x2_extract_lane::<1>(acc34),
+        )
+    }
+
+    // Unlike AVX2 and NEON, simd128 has a 64-bit lane multiply.
+    #[inline(always)]
+    fn round(acc: v128, lanes: v128) -> v128 {
+        let acc = u64x2_add(acc, u64x2_mul(lanes, u64x2_splat(PRIME64_2)));
+        let acc = v128_or(u64x2_shl(acc, 31), u64x2_shr(acc, 33));
+        u64x2_mul(acc, u64x2_splat(PRIME64_1))
+    }
+
+    // Run with `wasm-pack test --node -- --features unstable-simd` and
+    // `RUSTFLAGS="-C target-feature=+simd128"`.
+    #[cfg(test)]
+    mod tests {
+        use wasm_bindgen_test::wasm_bindgen_test;
+
+        use super::*;
+
+        #[wasm_bindgen_test]
+        fn test_wasm_process_stripes() {
+            crate::simd::tests::check_process_stripes(process_stripes);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{xxh64_slice, Xxh64, STRIPE_LEN_32};
+
+    // Checks a backend's stripe loop against the scalar one on every whole number of
+    // stripes up to 1 KiB.
+    pub(crate) fn check_process_stripes<F>(process_stripes: F)
+    where
+        F: Fn((u64, u64, u64, u64), &[u8]) -> (u64, u64, u64, u64),
+    {
+        let data: Vec<u8> = (0..1024u32).map(|i| (i * 31 + 7) as u8).collect();
+        let init = (1, 2, 3, u64::MAX);
+        for stripes in 0..=data.len() / STRIPE_LEN_32 {
+            let slice = &data[..stripes * STRIPE_LEN_32];
+            let mut scalar = init;
+            for stripe in crate::stripes(slice) {
+                scalar = Xxh64::process_stripe(scalar, stripe);
+            }
+            assert_eq!(scalar, process_stripes(init, slice), "{} stripes", stripes);
+        }
+    }
+
+    #[test]
+    fn test_backend_xxh64_slice() {
+        // The one-shot hash goes through the selected backend, the streaming digest always
+        // uses the scalar loop.
+        let data: Vec<u8> = (0..1024u32).map(|i| (i * 131 + 17) as u8).collect();
+        for len in 0..=data.len() {
+            let mut digest = Xxh64::with_seed(10);
+            digest.write(&data[..len]);
+            assert_eq!(digest.finish(), xxh64_slice(&data[..len], 10));
+        }
+    }
+
+    #[test]
+    fn test_backend() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7 + 3) as u8).collect();
+        let init = (1, 2, 3, u64::MAX);
+        assert_eq!(
+            process_stripes_scalar(init, &data),
+            backend().process_stripes(init, &data)
+        );
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                assert_eq!("avx2", crate::xxh64_backend_name());
+                return;
+            }
+        }
+        #[cfg(all(
+            target_arch = "aarch64",
+            target_endian = "little",
+            target_feature = "neon"
+        ))]
+        {
+            assert_eq!("neon", crate::xxh64_backend_name());
+            return;
+        }
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            assert_eq!("simd128", crate::xxh64_backend_name());
+            return;
+        }
+        #[allow(unreachable_code)]
+        {
+            assert_eq!("scalar", crate::xxh64_backend_name());
+        }
+    }
+}
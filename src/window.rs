@@ -0,0 +1,84 @@
+use crate::{xxh64_slice, Xxh64};
+
+// WindowHasher hashes a sliding window over the last `N` bytes it was given.
+//
+// XXH64 is not a rolling hash: every `roll` rehashes the whole window, so it costs O(N)
+// rather than O(1). It is a convenience for fixed-size windows, e.g. content-defined
+// chunking, where `N` is small.
+#[derive(Clone)]
+pub struct WindowHasher<const N: usize> {
+    seed: u64,
+    window: [u8; N],
+    // Index of the oldest byte once the window is full, and of the next free slot before.
+    pos: usize,
+    len: usize,
+}
+
+impl<const N: usize> WindowHasher<N> {
+    pub fn with_seed(seed: u64) -> WindowHasher<N> {
+        assert!(N > 0, "window size must be non-zero");
+        WindowHasher {
+            seed,
+            window: [0; N],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    // roll pushes `byte` into the window, dropping the oldest byte once the window is
+    // full, and returns the hash of the window. Until `N` bytes have been rolled in, the
+    // window is just the bytes seen so far.
+    pub fn roll(&mut self, byte: u8) -> u64 {
+        self.window[self.pos] = byte;
+        self.pos = (self.pos + 1) % N;
+        if self.len < N {
+            self.len += 1;
+        }
+        self.finish()
+    }
+
+    // finish returns the hash of the current window.
+    pub fn finish(&self) -> u64 {
+        if self.len < N {
+            return xxh64_slice(&self.window[..self.len], self.seed);
+        }
+        // The window wraps around the end of the ring buffer.
+        let mut digest = Xxh64::with_seed(self.seed);
+        digest.write(&self.window[self.pos..]);
+        digest.write(&self.window[..self.pos]);
+        digest.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_hasher() {
+        let data: Vec<u8> = (0..500u32).map(|i| (i * 31 + 7) as u8).collect();
+
+        let mut window = WindowHasher::<48>::with_seed(10);
+        assert_eq!(xxh64_slice(b"", 10), window.finish());
+        for (i, &byte) in data.iter().enumerate() {
+            let start = (i + 1).saturating_sub(48);
+            assert_eq!(
+                xxh64_slice(&data[start..=i], 10),
+                window.roll(byte),
+                "i {}",
+                i
+            );
+        }
+
+        let mut window = WindowHasher::<1>::with_seed(0);
+        for &byte in &data[..10] {
+            assert_eq!(xxh64_slice(&[byte], 0), window.roll(byte));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "window size must be non-zero")]
+    fn test_window_hasher_empty() {
+        WindowHasher::<0>::with_seed(0);
+    }
+}
@@ -0,0 +1,146 @@
+use std::io::{self, Write};
+
+use crate::Xxh64;
+
+// HashingWriter forwards writes to an inner writer while hashing the bytes it accepted,
+// so a file can be generated and checksummed in one pass.
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    digest: Xxh64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W, seed: u64) -> HashingWriter<W> {
+        HashingWriter {
+            inner,
+            digest: Xxh64::with_seed(seed),
+        }
+    }
+
+    // finish returns the digest of the bytes written so far.
+    pub fn finish(&self) -> u64 {
+        self.digest.finish()
+    }
+
+    // into_inner returns the inner writer and the digest of everything written to it.
+    // It does not flush the inner writer.
+    pub fn into_inner(self) -> (W, u64) {
+        let hash = self.digest.finish();
+        (self.inner, hash)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Only hash what the inner writer took; the caller retries the rest.
+        let n = self.inner.write(buf)?;
+        self.digest.write(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// VerifyingWriter is a `HashingWriter` that checks the bytes it accepted against an
+// expected XXH64, so content can be copied and verified in one pass.
+pub struct VerifyingWriter<W: Write> {
+    inner: HashingWriter<W>,
+}
+
+impl<W: Write> VerifyingWriter<W> {
+    pub fn new(inner: W, seed: u64) -> VerifyingWriter<W> {
+        VerifyingWriter {
+            inner: HashingWriter::new(inner, seed),
+        }
+    }
+
+    // verify reports whether the bytes written so far hash to `expected`.
+    pub fn verify(&self, expected: u64) -> bool {
+        self.inner.digest.finish_eq(expected)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner.inner
+    }
+}
+
+impl<W: Write> Write for VerifyingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+
+    use super::*;
+    use crate::xxh64_slice;
+
+    #[test]
+    fn test_verifying_writer() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 + 1) as u8).collect();
+        let expected = xxh64_slice(&data, 10);
+
+        let mut writer = VerifyingWriter::new(Vec::new(), 10);
+        io::copy(&mut Cursor::new(&data), &mut writer).unwrap();
+        assert!(writer.verify(expected));
+        assert_eq!(data, writer.into_inner());
+
+        let mut corrupted = data.clone();
+        corrupted[5000] ^= 1;
+        let mut writer = VerifyingWriter::new(Vec::new(), 10);
+        io::copy(&mut Cursor::new(&corrupted), &mut writer).unwrap();
+        assert!(!writer.verify(expected));
+    }
+
+    // A writer that accepts at most 7 bytes per call.
+    struct Short(Vec<u8>);
+
+    impl Write for Short {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(7);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_verifying_writer_short_writes() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let mut writer = VerifyingWriter::new(Short(Vec::new()), 0);
+        writer.write_all(&data).unwrap();
+        assert!(writer.verify(xxh64_slice(&data, 0)));
+    }
+
+    #[test]
+    fn test_hashing_writer() {
+        let mut writer = HashingWriter::new(Vec::new(), 10);
+        for i in 0..1000u32 {
+            writeln!(writer, "line {}", i).unwrap();
+        }
+        assert_eq!(0, writer.write(&[]).unwrap());
+        let partial = writer.finish();
+        let (contents, hash) = writer.into_inner();
+        assert_eq!(partial, hash);
+        assert_eq!(xxh64_slice(&contents, 10), hash);
+        assert!(contents.ends_with(b"line 999\n"));
+
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let mut writer = HashingWriter::new(Short(Vec::new()), 0);
+        writer.write_all(&data).unwrap();
+        let (inner, hash) = writer.into_inner();
+        assert_eq!(data, inner.0);
+        assert_eq!(xxh64_slice(&data, 0), hash);
+    }
+}
@@ -0,0 +1,1326 @@
+use core::cmp::Ordering;
+use core::hash::Hasher;
+
+use crate::XxHasher;
+
+const PRIME32_1: u64 = 0x9E3779B1;
+const PRIME32_2: u64 = 0x85EBCA77;
+const PRIME32_3: u64 = 0xC2B2AE3D;
+const PRIME64_1: u64 = 0x9E3779B185EBCA87;
+const PRIME64_2: u64 = 0xC2B2AE3D27D4EB4F;
+const PRIME64_3: u64 = 0x165667B19E3779F9;
+const PRIME64_4: u64 = 0x85EBCA77C2B2AE63;
+const PRIME64_5: u64 = 0x27D4EB2F165667C5;
+const PRIME_MX1: u64 = 0x165667919E3779F9;
+const PRIME_MX2: u64 = 0x9FB21C651E98DF25;
+
+const STRIPE_LEN_64: usize = 64;
+const SECRET_CONSUME_RATE: usize = 8;
+const ACC_NB: usize = STRIPE_LEN_64 / 8;
+const SECRET_SIZE_MIN: usize = 136;
+const SECRET_MERGEACCS_START: usize = 11;
+const SECRET_LASTACC_START: usize = 7;
+const MIDSIZE_MAX: usize = 240;
+const MIDSIZE_STARTOFFSET: usize = 3;
+const MIDSIZE_LASTOFFSET: usize = 17;
+
+const DEFAULT_SECRET_SIZE: usize = 192;
+// Largest secret `Xxh3_64::with_secret` can store.
+const SECRET_SIZE_MAX: usize = 256;
+const INTERNAL_BUFFER_SIZE: usize = 256;
+const INTERNAL_BUFFER_STRIPES: usize = INTERNAL_BUFFER_SIZE / STRIPE_LEN_64;
+
+// The default secret, taken from the reference implementation.
+const DEFAULT_SECRET: [u8; DEFAULT_SECRET_SIZE] = [
+    0xb8, 0xfe, 0x6c, 0x39, 0x23, 0xa4, 0x4b, 0xbe, 0x7c, 0x01, 0x81, 0x2c, 0xf7, 0x21, 0xad, 0x1c,
+    0xde, 0xd4, 0x6d, 0xe9, 0x83, 0x90, 0x97, 0xdb, 0x72, 0x40, 0xa4, 0xa4, 0xb7, 0xb3, 0x67, 0x1f,
+    0xcb, 0x79, 0xe6, 0x4e, 0xcc, 0xc0, 0xe5, 0x78, 0x82, 0x5a, 0xd0, 0x7d, 0xcc, 0xff, 0x72, 0x21,
+    0xb8, 0x08, 0x46, 0x74, 0xf7, 0x43, 0x24, 0x8e, 0xe0, 0x35, 0x90, 0xe6, 0x81, 0x3a, 0x26, 0x4c,
+    0x3c, 0x28, 0x52, 0xbb, 0x91, 0xc3, 0x00, 0xcb, 0x88, 0xd0, 0x65, 0x8b, 0x1b, 0x53, 0x2e, 0xa3,
+    0x71, 0x64, 0x48, 0x97, 0xa2, 0x0d, 0xf9, 0x4e, 0x38, 0x19, 0xef, 0x46, 0xa9, 0xde, 0xac, 0xd8,
+    0xa8, 0xfa, 0x76, 0x3f, 0xe3, 0x9c, 0x34, 0x3f, 0xf9, 0xdc, 0xbb, 0xc7, 0xc7, 0x0b, 0x4f, 0x1d,
+    0x8a, 0x51, 0xe0, 0x4b, 0xcd, 0xb4, 0x59, 0x31, 0xc8, 0x9f, 0x7e, 0xc9, 0xd9, 0x78, 0x73, 0x64,
+    0xea, 0xc5, 0xac, 0x83, 0x34, 0xd3, 0xeb, 0xc3, 0xc5, 0x81, 0xa0, 0xff, 0xfa, 0x13, 0x63, 0xeb,
+    0x17, 0x0d, 0xdd, 0x51, 0xb7, 0xf0, 0xda, 0x49, 0xd3, 0x16, 0x55, 0x26, 0x29, 0xd4, 0x68, 0x9e,
+    0x2b, 0x16, 0xbe, 0x58, 0x7d, 0x47, 0xa1, 0xfc, 0x8f, 0xf8, 0xb8, 0xd1, 0x7a, 0xd0, 0x31, 0xce,
+    0x45, 0xcb, 0x3a, 0x8f, 0x95, 0x16, 0x04, 0x28, 0xaf, 0xd7, 0xfb, 0xca, 0xbb, 0x4b, 0x40, 0x7e,
+];
+
+const INIT_ACC: [u64; ACC_NB] = [
+    PRIME32_3, PRIME64_1, PRIME64_2, PRIME64_3, PRIME64_4, PRIME32_2, PRIME64_5, PRIME32_1,
+];
+
+pub fn xxh3_64_slice(data: &[u8], seed: u64) -> u64 {
+    if data.len() <= MIDSIZE_MAX {
+        hash_short_64(data, &DEFAULT_SECRET, seed)
+    } else if seed == 0 {
+        hash_long_64(data, &DEFAULT_SECRET)
+    } else {
+        hash_long_64(data, &custom_secret(seed))
+    }
+}
+
+// xxh3_64_with_secret hashes `data` with a custom secret in place of the default one.
+// It panics if the secret is shorter than 136 bytes.
+pub fn xxh3_64_with_secret(data: &[u8], secret: &[u8]) -> u64 {
+    assert!(
+        secret.len() >= SECRET_SIZE_MIN,
+        "secret must be at least {} bytes, got {}",
+        SECRET_SIZE_MIN,
+        secret.len()
+    );
+    if data.len() <= MIDSIZE_MAX {
+        hash_short_64(data, secret, 0)
+    } else {
+        hash_long_64(data, secret)
+    }
+}
+
+// xxh3_64_with_secret_and_seed is `XXH3_64bits_withSecretandSeed`: inputs of up to 240
+// bytes are hashed like `xxh3_64_slice` with `seed` and the default secret, longer ones
+// like `xxh3_64_with_secret` with `secret`, which should then be derived from the seed,
+// e.g. with `xxh3_generate_secret`. It panics if the secret is shorter than 136 bytes.
+pub fn xxh3_64_with_secret_and_seed(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    assert!(
+        secret.len() >= SECRET_SIZE_MIN,
+        "secret must be at least {} bytes, got {}",
+        SECRET_SIZE_MIN,
+        secret.len()
+    );
+    if data.len() <= MIDSIZE_MAX {
+        hash_short_64(data, &DEFAULT_SECRET, seed)
+    } else {
+        hash_long_64(data, secret)
+    }
+}
+
+// xxh3_generate_secret returns the secret that seeded hashing derives from `seed`, like
+// `XXH3_initCustomSecret`. `xxh3_64_with_secret` with this secret equals
+// `xxh3_64_slice` with the seed for inputs longer than 240 bytes, without deriving the
+// secret on every call. Shorter inputs are hashed from the seed itself and differ.
+pub fn xxh3_generate_secret(seed: u64) -> [u8; DEFAULT_SECRET_SIZE] {
+    custom_secret(seed)
+}
+
+pub fn xxh3_128_slice(data: &[u8], seed: u64) -> u128 {
+    if data.len() <= MIDSIZE_MAX {
+        hash_short_128(data, &DEFAULT_SECRET, seed)
+    } else if seed == 0 {
+        hash_long_128(data, &DEFAULT_SECRET)
+    } else {
+        hash_long_128(data, &custom_secret(seed))
+    }
+}
+
+// xxh3_64_and_128 returns both `xxh3_64_slice` and `xxh3_128_slice` of `data`. Inputs
+// longer than 240 bytes are accumulated only once, since the 64-bit hash is the low half
+// of the 128-bit one there.
+pub fn xxh3_64_and_128(data: &[u8], seed: u64) -> (u64, u128) {
+    if data.len() <= MIDSIZE_MAX {
+        return (
+            hash_short_64(data, &DEFAULT_SECRET, seed),
+            hash_short_128(data, &DEFAULT_SECRET, seed),
+        );
+    }
+    let custom;
+    let secret: &[u8] = if seed == 0 {
+        &DEFAULT_SECRET
+    } else {
+        custom = custom_secret(seed);
+        &custom
+    };
+    let hash = merge_accs_128(&hash_long(data, secret), secret, data.len() as u64);
+    (hash as u64, hash)
+}
+
+// xxh3_128_hex returns `xxh3_128_slice(data, seed)` as 32 lowercase hex digits, keeping
+// leading zeros.
+#[cfg(feature = "std")]
+pub fn xxh3_128_hex(data: &[u8], seed: u64) -> String {
+    format!("{:032x}", xxh3_128_slice(data, seed))
+}
+
+// Appended to the input for the second half of `xxh3_256`.
+const XXH3_256_TWEAK: &[u8] = b"xxh3-256 domain separation";
+
+// xxh3_256 is a non-standard 256-bit hash, not part of xxHash and not produced by any
+// other implementation. The first 16 bytes are the canonical `xxh3_128_slice` of
+// `data`, so truncating it gives the standard XXH3-128. The last 16 bytes are the
+// canonical XXH3-128 of `data` followed by a fixed domain-separation suffix, computed
+// without copying `data`. It widens the keyspace against accidental collisions; like
+// XXH3 itself it is not collision-resistant against deliberate attacks.
+pub fn xxh3_256(data: &[u8], seed: u64) -> [u8; 32] {
+    let mut tweaked = Xxh128::with_seed(seed);
+    tweaked.write(data);
+    tweaked.write(XXH3_256_TWEAK);
+
+    let mut out = [0; 32];
+    out[..16].copy_from_slice(&xxh128_canonical(xxh3_128_slice(data, seed)));
+    out[16..].copy_from_slice(&xxh128_canonical(tweaked.finish()));
+    out
+}
+
+pub fn xxh128_canonical(hash: u128) -> [u8; 16] {
+    // The canonical representation is big-endian, matching `XXH128_canonicalFromHash`:
+    // the high 64 bits first, then the low 64 bits.
+    hash.to_be_bytes()
+}
+
+pub fn xxh128_from_canonical(bytes: &[u8; 16]) -> u128 {
+    u128::from_be_bytes(*bytes)
+}
+
+// xxh128_cmp orders two 128-bit hashes like `XXH128_cmp`: by the high 64 bits, then by
+// the low 64 bits. This is the same as comparing the `u128` values.
+pub fn xxh128_cmp(a: u128, b: u128) -> Ordering {
+    let high = ((a >> 64) as u64).cmp(&((b >> 64) as u64));
+    high.then_with(|| (a as u64).cmp(&(b as u64)))
+}
+
+// Xxh3_64 represents the streaming XXH3 digest algorithm(64-bits).
+#[derive(Clone)]
+pub struct Xxh3_64 {
+    seed: u64,
+    // Only the first `secret_len` bytes are used.
+    secret: [u8; SECRET_SIZE_MAX],
+    secret_len: usize,
+    acc: [u64; ACC_NB],
+    // Input is staged here and only accumulated once more data arrives, so the
+    // last stripe is always available to `finish`.
+    buffer: [u8; INTERNAL_BUFFER_SIZE],
+    buffer_len: usize,
+    // Stripes accumulated into the current block, before the next scramble.
+    nb_stripes_so_far: usize,
+    // u64 rather than usize, which would overflow after 4 GiB of input on 32-bit targets.
+    total_len: u64,
+}
+
+impl Xxh3_64 {
+    pub fn with_seed(seed: u64) -> Xxh3_64 {
+        let mut secret = [0; SECRET_SIZE_MAX];
+        secret[..DEFAULT_SECRET_SIZE].copy_from_slice(&if seed == 0 {
+            DEFAULT_SECRET
+        } else {
+            custom_secret(seed)
+        });
+        Xxh3_64::with_secret_array(seed, secret, DEFAULT_SECRET_SIZE)
+    }
+
+    // with_secret creates a digest that hashes like `xxh3_64_with_secret`, e.g. with a
+    // secret computed once by `xxh3_generate_secret`. The secret is copied into the
+    // digest. It panics if the secret is shorter than 136 or longer than 256 bytes.
+    pub fn with_secret(secret: &[u8]) -> Xxh3_64 {
+        assert!(
+            (SECRET_SIZE_MIN..=SECRET_SIZE_MAX).contains(&secret.len()),
+            "secret must be {} to {} bytes, got {}",
+            SECRET_SIZE_MIN,
+            SECRET_SIZE_MAX,
+            secret.len()
+        );
+        let mut stored = [0; SECRET_SIZE_MAX];
+        stored[..secret.len()].copy_from_slice(secret);
+        Xxh3_64::with_secret_array(0, stored, secret.len())
+    }
+
+    fn with_secret_array(seed: u64, secret: [u8; SECRET_SIZE_MAX], secret_len: usize) -> Xxh3_64 {
+        Xxh3_64 {
+            seed,
+            secret,
+            secret_len,
+            acc: INIT_ACC,
+            buffer: [0; INTERNAL_BUFFER_SIZE],
+            buffer_len: 0,
+            nb_stripes_so_far: 0,
+            total_len: 0,
+        }
+    }
+
+    pub fn write(&mut self, mut bytes: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(bytes.len() as u64);
+
+        if self.buffer_len + bytes.len() <= INTERNAL_BUFFER_SIZE {
+            self.buffer[self.buffer_len..self.buffer_len + bytes.len()].copy_from_slice(bytes);
+            self.buffer_len += bytes.len();
+            return;
+        }
+
+        if self.buffer_len > 0 {
+            let (head, rest) = bytes.split_at(INTERNAL_BUFFER_SIZE - self.buffer_len);
+            self.buffer[self.buffer_len..].copy_from_slice(head);
+            consume_stripes(
+                &mut self.acc,
+                &mut self.nb_stripes_so_far,
+                &self.secret[..self.secret_len],
+                &self.buffer,
+                INTERNAL_BUFFER_STRIPES,
+            );
+            self.buffer_len = 0;
+            bytes = rest;
+        }
+
+        // Keep at least one byte back so the final block is handled by `finish`.
+        let mut offset = 0;
+        while bytes.len() - offset > INTERNAL_BUFFER_SIZE {
+            consume_stripes(
+                &mut self.acc,
+                &mut self.nb_stripes_so_far,
+                &self.secret[..self.secret_len],
+                &bytes[offset..],
+                INTERNAL_BUFFER_STRIPES,
+            );
+            offset += INTERNAL_BUFFER_SIZE;
+        }
+        if offset > 0 {
+            // `finish` needs the previous stripe if less than a stripe follows.
+            self.buffer[INTERNAL_BUFFER_SIZE - STRIPE_LEN_64..]
+                .copy_from_slice(&bytes[offset - STRIPE_LEN_64..offset]);
+        }
+
+        self.buffer[..bytes.len() - offset].copy_from_slice(&bytes[offset..]);
+        self.buffer_len = bytes.len() - offset;
+    }
+
+    pub fn finish(&self) -> u64 {
+        if self.total_len <= MIDSIZE_MAX as u64 {
+            return hash_short_64(
+                &self.buffer[..self.total_len as usize],
+                self.short_secret(),
+                self.seed,
+            );
+        }
+
+        merge_accs(
+            &self.final_accs(),
+            &self.secret()[SECRET_MERGEACCS_START..],
+            self.total_len.wrapping_mul(PRIME64_1),
+        )
+    }
+
+    fn secret(&self) -> &[u8] {
+        &self.secret[..self.secret_len]
+    }
+
+    // Returns the secret for inputs of up to 240 bytes. Seeded digests hash those with
+    // the default secret and the seed itself rather than the derived secret. Digests
+    // created with a secret have seed 0 and use their own.
+    fn short_secret(&self) -> &[u8] {
+        if self.seed == 0 {
+            self.secret()
+        } else {
+            &DEFAULT_SECRET
+        }
+    }
+
+    // Returns the accumulators with the buffered input and the last stripe consumed, for
+    // inputs longer than 240 bytes.
+    fn final_accs(&self) -> [u64; ACC_NB] {
+        let mut acc = self.acc;
+        let secret = self.secret();
+        let secret_last = secret.len() - STRIPE_LEN_64 - SECRET_LASTACC_START;
+        if self.buffer_len >= STRIPE_LEN_64 {
+            let nb_stripes = (self.buffer_len - 1) / STRIPE_LEN_64;
+            let mut nb_stripes_so_far = self.nb_stripes_so_far;
+            consume_stripes(
+                &mut acc,
+                &mut nb_stripes_so_far,
+                secret,
+                &self.buffer,
+                nb_stripes,
+            );
+            accumulate_512(
+                &mut acc,
+                &self.buffer[self.buffer_len - STRIPE_LEN_64..],
+                &secret[secret_last..],
+            );
+        } else {
+            // The last stripe spans the end of the previous block and the buffered bytes.
+            let mut last_stripe = [0; STRIPE_LEN_64];
+            let catchup = STRIPE_LEN_64 - self.buffer_len;
+            last_stripe[..catchup].copy_from_slice(&self.buffer[INTERNAL_BUFFER_SIZE - catchup..]);
+            last_stripe[catchup..].copy_from_slice(&self.buffer[..self.buffer_len]);
+            accumulate_512(&mut acc, &last_stripe, &secret[secret_last..]);
+        }
+        acc
+    }
+}
+
+impl Hasher for Xxh3_64 {
+    fn finish(&self) -> u64 {
+        self.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+}
+
+impl XxHasher for Xxh3_64 {
+    type Output = u64;
+
+    fn with_seed(seed: u64) -> Xxh3_64 {
+        Xxh3_64::with_seed(seed)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+
+    fn digest(&self) -> u64 {
+        self.finish()
+    }
+}
+
+impl Default for Xxh3_64 {
+    fn default() -> Self {
+        Xxh3_64::with_seed(0)
+    }
+}
+
+// Accumulates `nb_stripes` stripes of `data`, scrambling whenever a block fills up.
+// `nb_stripes_so_far` counts the stripes already in the current block. It takes the
+// digest's fields separately so callers can pass its own buffer without copying it.
+fn consume_stripes(
+    acc: &mut [u64; ACC_NB],
+    nb_stripes_so_far: &mut usize,
+    secret: &[u8],
+    data: &[u8],
+    nb_stripes: usize,
+) {
+    let nb_stripes_per_block = (secret.len() - STRIPE_LEN_64) / SECRET_CONSUME_RATE;
+    let to_end_of_block = nb_stripes_per_block - *nb_stripes_so_far;
+    if nb_stripes >= to_end_of_block {
+        accumulate(
+            acc,
+            data,
+            &secret[*nb_stripes_so_far * SECRET_CONSUME_RATE..],
+            to_end_of_block,
+        );
+        scramble_acc(acc, &secret[secret.len() - STRIPE_LEN_64..]);
+        accumulate(
+            acc,
+            &data[to_end_of_block * STRIPE_LEN_64..],
+            secret,
+            nb_stripes - to_end_of_block,
+        );
+        *nb_stripes_so_far = nb_stripes - to_end_of_block;
+    } else {
+        accumulate(
+            acc,
+            data,
+            &secret[*nb_stripes_so_far * SECRET_CONSUME_RATE..],
+            nb_stripes,
+        );
+        *nb_stripes_so_far += nb_stripes;
+    }
+}
+
+// Xxh128 represents the streaming XXH3 digest algorithm(128-bits). It buffers input
+// exactly like `Xxh3_64` and only differs in the finalization.
+#[derive(Clone)]
+pub struct Xxh128 {
+    state: Xxh3_64,
+}
+
+impl Xxh128 {
+    pub fn with_seed(seed: u64) -> Xxh128 {
+        Xxh128 {
+            state: Xxh3_64::with_seed(seed),
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.state.write(bytes);
+    }
+
+    pub fn finish(&self) -> u128 {
+        let state = &self.state;
+        if state.total_len <= MIDSIZE_MAX as u64 {
+            return hash_short_128(
+                &state.buffer[..state.total_len as usize],
+                state.short_secret(),
+                state.seed,
+            );
+        }
+        merge_accs_128(&state.final_accs(), state.secret(), state.total_len)
+    }
+
+    // finish_hex returns `finish` as 32 lowercase hex digits, keeping leading zeros.
+    #[cfg(feature = "std")]
+    pub fn finish_hex(&self) -> String {
+        format!("{:032x}", self.finish())
+    }
+
+    // finish_u64 returns the low 64 bits of `finish`, which is what the `Hasher` impl
+    // reports.
+    pub fn finish_u64(&self) -> u64 {
+        self.finish() as u64
+    }
+}
+
+impl Hasher for Xxh128 {
+    fn finish(&self) -> u64 {
+        self.finish_u64()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+}
+
+impl XxHasher for Xxh128 {
+    type Output = u128;
+
+    fn with_seed(seed: u64) -> Xxh128 {
+        Xxh128::with_seed(seed)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+
+    fn digest(&self) -> u128 {
+        self.finish()
+    }
+}
+
+impl Default for Xxh128 {
+    fn default() -> Self {
+        Xxh128::with_seed(0)
+    }
+}
+
+#[inline(always)]
+fn hash_short_64(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    if len <= 16 {
+        if len > 8 {
+            hash_9to16_64(data, secret, seed)
+        } else if len >= 4 {
+            hash_4to8_64(data, secret, seed)
+        } else if len > 0 {
+            hash_1to3_64(data, secret, seed)
+        } else {
+            xxh64_avalanche(seed ^ (read_u64(secret, 56) ^ read_u64(secret, 64)))
+        }
+    } else if len <= 128 {
+        hash_17to128_64(data, secret, seed)
+    } else {
+        hash_129to240_64(data, secret, seed)
+    }
+}
+
+#[inline(always)]
+fn hash_1to3_64(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let c1 = data[0] as u32;
+    let c2 = data[len >> 1] as u32;
+    let c3 = data[len - 1] as u32;
+    let combined = (c1 << 16) | (c2 << 24) | c3 | ((len as u32) << 8);
+    let bitflip = ((read_u32(secret, 0) ^ read_u32(secret, 4)) as u64).wrapping_add(seed);
+    xxh64_avalanche(combined as u64 ^ bitflip)
+}
+
+#[inline(always)]
+fn hash_4to8_64(data: &[u8], secret: &[u8], mut seed: u64) -> u64 {
+    let len = data.len();
+    seed ^= ((seed as u32).swap_bytes() as u64) << 32;
+    let input1 = read_u32(data, 0) as u64;
+    let input2 = read_u32(data, len - 4) as u64;
+    let bitflip = (read_u64(secret, 8) ^ read_u64(secret, 16)).wrapping_sub(seed);
+    let input64 = input2.wrapping_add(input1 << 32);
+    rrmxmx(input64 ^ bitflip, len as u64)
+}
+
+#[inline(always)]
+fn hash_9to16_64(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let bitflip1 = (read_u64(secret, 24) ^ read_u64(secret, 32)).wrapping_add(seed);
+    let bitflip2 = (read_u64(secret, 40) ^ read_u64(secret, 48)).wrapping_sub(seed);
+    let input_lo = read_u64(data, 0) ^ bitflip1;
+    let input_hi = read_u64(data, len - 8) ^ bitflip2;
+    let acc = (len as u64)
+        .wrapping_add(input_lo.swap_bytes())
+        .wrapping_add(input_hi)
+        .wrapping_add(mul128_fold64(input_lo, input_hi));
+    avalanche(acc)
+}
+
+#[inline(always)]
+fn hash_17to128_64(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+    if len > 32 {
+        if len > 64 {
+            if len > 96 {
+                acc = acc.wrapping_add(mix16(&data[48..], &secret[96..], seed));
+                acc = acc.wrapping_add(mix16(&data[len - 64..], &secret[112..], seed));
+            }
+            acc = acc.wrapping_add(mix16(&data[32..], &secret[64..], seed));
+            acc = acc.wrapping_add(mix16(&data[len - 48..], &secret[80..], seed));
+        }
+        acc = acc.wrapping_add(mix16(&data[16..], &secret[32..], seed));
+        acc = acc.wrapping_add(mix16(&data[len - 32..], &secret[48..], seed));
+    }
+    acc = acc.wrapping_add(mix16(data, secret, seed));
+    acc = acc.wrapping_add(mix16(&data[len - 16..], &secret[16..], seed));
+    avalanche(acc)
+}
+
+#[inline(always)]
+fn hash_129to240_64(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let nb_rounds = len / 16;
+    let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+    for i in 0..8 {
+        acc = acc.wrapping_add(mix16(&data[16 * i..], &secret[16 * i..], seed));
+    }
+    acc = avalanche(acc);
+    for i in 8..nb_rounds {
+        acc = acc.wrapping_add(mix16(
+            &data[16 * i..],
+            &secret[16 * (i - 8) + MIDSIZE_STARTOFFSET..],
+            seed,
+        ));
+    }
+    // Last 16 bytes
+    acc = acc.wrapping_add(mix16(
+        &data[len - 16..],
+        &secret[SECRET_SIZE_MIN - MIDSIZE_LASTOFFSET..],
+        seed,
+    ));
+    avalanche(acc)
+}
+
+#[inline(always)]
+fn hash_long_64(data: &[u8], secret: &[u8]) -> u64 {
+    let acc = hash_long(data, secret);
+    merge_accs(
+        &acc,
+        &secret[SECRET_MERGEACCS_START..],
+        (data.len() as u64).wrapping_mul(PRIME64_1),
+    )
+}
+
+#[inline(always)]
+fn hash_short_128(data: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = data.len();
+    if len <= 16 {
+        if len > 8 {
+            hash_9to16_128(data, secret, seed)
+        } else if len >= 4 {
+            hash_4to8_128(data, secret, seed)
+        } else if len > 0 {
+            hash_1to3_128(data, secret, seed)
+        } else {
+            let low = xxh64_avalanche(seed ^ read_u64(secret, 64) ^ read_u64(secret, 72));
+            let high = xxh64_avalanche(seed ^ read_u64(secret, 80) ^ read_u64(secret, 88));
+            to_u128(low, high)
+        }
+    } else if len <= 128 {
+        hash_17to128_128(data, secret, seed)
+    } else {
+        hash_129to240_128(data, secret, seed)
+    }
+}
+
+#[inline(always)]
+fn hash_1to3_128(data: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = data.len();
+    let c1 = data[0] as u32;
+    let c2 = data[len >> 1] as u32;
+    let c3 = data[len - 1] as u32;
+    let combined_lo = (c1 << 16) | (c2 << 24) | c3 | ((len as u32) << 8);
+    let combined_hi = combined_lo.swap_bytes().rotate_left(13);
+    let bitflip_lo = ((read_u32(secret, 0) ^ read_u32(secret, 4)) as u64).wrapping_add(seed);
+    let bitflip_hi = ((read_u32(secret, 8) ^ read_u32(secret, 12)) as u64).wrapping_sub(seed);
+    let low = xxh64_avalanche(combined_lo as u64 ^ bitflip_lo);
+    let high = xxh64_avalanche(combined_hi as u64 ^ bitflip_hi);
+    to_u128(low, high)
+}
+
+#[inline(always)]
+fn hash_4to8_128(data: &[u8], secret: &[u8], mut seed: u64) -> u128 {
+    let len = data.len();
+    seed ^= ((seed as u32).swap_bytes() as u64) << 32;
+    let input_lo = read_u32(data, 0) as u64;
+    let input_hi = read_u32(data, len - 4) as u64;
+    let input64 = input_lo.wrapping_add(input_hi << 32);
+    let bitflip = (read_u64(secret, 16) ^ read_u64(secret, 24)).wrapping_add(seed);
+    let keyed = input64 ^ bitflip;
+
+    // Shift len to the left to ensure it is even, this avoids even multiplies.
+    let m128 = (keyed as u128).wrapping_mul(PRIME64_1.wrapping_add((len as u64) << 2) as u128);
+    let mut low = m128 as u64;
+    let mut high = (m128 >> 64) as u64;
+    high = high.wrapping_add(low << 1);
+    low ^= high >> 3;
+    low ^= low >> 35;
+    low = low.wrapping_mul(PRIME_MX2);
+    low ^= low >> 28;
+    high = avalanche(high);
+    to_u128(low, high)
+}
+
+#[inline(always)]
+fn hash_9to16_128(data: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = data.len();
+    let bitflip_lo = (read_u64(secret, 32) ^ read_u64(secret, 40)).wrapping_sub(seed);
+    let bitflip_hi = (read_u64(secret, 48) ^ read_u64(secret, 56)).wrapping_add(seed);
+    let input_lo = read_u64(data, 0);
+    let mut input_hi = read_u64(data, len - 8);
+    let m128 = ((input_lo ^ input_hi ^ bitflip_lo) as u128).wrapping_mul(PRIME64_1 as u128);
+    let mut m128_lo = m128 as u64;
+    let mut m128_hi = (m128 >> 64) as u64;
+    m128_lo = m128_lo.wrapping_add(((len as u64) - 1) << 54);
+    input_hi ^= bitflip_hi;
+    m128_hi = m128_hi
+        .wrapping_add(input_hi)
+        .wrapping_add((input_hi as u32 as u64).wrapping_mul(PRIME32_2 - 1));
+    m128_lo ^= m128_hi.swap_bytes();
+
+    let h128 = (m128_lo as u128).wrapping_mul(PRIME64_2 as u128);
+    let low = h128 as u64;
+    let high = ((h128 >> 64) as u64).wrapping_add(m128_hi.wrapping_mul(PRIME64_2));
+    to_u128(avalanche(low), avalanche(high))
+}
+
+#[inline(always)]
+fn hash_17to128_128(data: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = data.len();
+    let mut acc = ((len as u64).wrapping_mul(PRIME64_1), 0u64);
+    if len > 32 {
+        if len > 64 {
+            if len > 96 {
+                acc = mix32(acc, &data[48..], &data[len - 64..], &secret[96..], seed);
+            }
+            acc = mix32(acc, &data[32..], &data[len - 48..], &secret[64..], seed);
+        }
+        acc = mix32(acc, &data[16..], &data[len - 32..], &secret[32..], seed);
+    }
+    acc = mix32(acc, data, &data[len - 16..], secret, seed);
+    finalize_mid_128(acc, len, seed)
+}
+
+#[inline(always)]
+fn hash_129to240_128(data: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = data.len();
+    let nb_rounds = len / 32;
+    let mut acc = ((len as u64).wrapping_mul(PRIME64_1), 0u64);
+    for i in 0..4 {
+        acc = mix32(
+            acc,
+            &data[32 * i..],
+            &data[32 * i + 16..],
+            &secret[32 * i..],
+            seed,
+        );
+    }
+    acc = (avalanche(acc.0), avalanche(acc.1));
+    for i in 4..nb_rounds {
+        acc = mix32(
+            acc,
+            &data[32 * i..],
+            &data[32 * i + 16..],
+            &secret[MIDSIZE_STARTOFFSET + 32 * (i - 4)..],
+            seed,
+        );
+    }
+    // Last bytes
+    acc = mix32(
+        acc,
+        &data[len - 16..],
+        &data[len - 32..],
+        &secret[SECRET_SIZE_MIN - MIDSIZE_LASTOFFSET - 16..],
+        0u64.wrapping_sub(seed),
+    );
+    finalize_mid_128(acc, len, seed)
+}
+
+#[inline(always)]
+fn finalize_mid_128(acc: (u64, u64), len: usize, seed: u64) -> u128 {
+    let low = acc.0.wrapping_add(acc.1);
+    let high = acc
+        .0
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(acc.1.wrapping_mul(PRIME64_4))
+        .wrapping_add((len as u64).wrapping_sub(seed).wrapping_mul(PRIME64_2));
+    to_u128(avalanche(low), 0u64.wrapping_sub(avalanche(high)))
+}
+
+#[inline(always)]
+fn hash_long_128(data: &[u8], secret: &[u8]) -> u128 {
+    merge_accs_128(&hash_long(data, secret), secret, data.len() as u64)
+}
+
+// The 128-bit finalization of long inputs. Its low half is the 64-bit hash.
+#[inline(always)]
+fn merge_accs_128(acc: &[u64; ACC_NB], secret: &[u8], len: u64) -> u128 {
+    let low = merge_accs(
+        acc,
+        &secret[SECRET_MERGEACCS_START..],
+        len.wrapping_mul(PRIME64_1),
+    );
+    let high = merge_accs(
+        acc,
+        &secret[secret.len() - STRIPE_LEN_64 - SECRET_MERGEACCS_START..],
+        !len.wrapping_mul(PRIME64_2),
+    );
+    to_u128(low, high)
+}
+
+// Runs the accumulation loop over the whole input, returning the accumulators.
+fn hash_long(data: &[u8], secret: &[u8]) -> [u64; ACC_NB] {
+    let mut acc = INIT_ACC;
+    let nb_stripes_per_block = (secret.len() - STRIPE_LEN_64) / SECRET_CONSUME_RATE;
+    let block_len = STRIPE_LEN_64 * nb_stripes_per_block;
+    let nb_blocks = (data.len() - 1) / block_len;
+
+    for n in 0..nb_blocks {
+        accumulate(
+            &mut acc,
+            &data[n * block_len..],
+            secret,
+            nb_stripes_per_block,
+        );
+        scramble_acc(&mut acc, &secret[secret.len() - STRIPE_LEN_64..]);
+    }
+
+    // Last partial block
+    let nb_stripes = ((data.len() - 1) - block_len * nb_blocks) / STRIPE_LEN_64;
+    accumulate(&mut acc, &data[nb_blocks * block_len..], secret, nb_stripes);
+
+    // Last stripe
+    accumulate_512(
+        &mut acc,
+        &data[data.len() - STRIPE_LEN_64..],
+        &secret[secret.len() - STRIPE_LEN_64 - SECRET_LASTACC_START..],
+    );
+    acc
+}
+
+#[inline(always)]
+fn accumulate(acc: &mut [u64; ACC_NB], data: &[u8], secret: &[u8], nb_stripes: usize) {
+    for n in 0..nb_stripes {
+        accumulate_512(
+            acc,
+            &data[n * STRIPE_LEN_64..],
+            &secret[n * SECRET_CONSUME_RATE..],
+        );
+    }
+}
+
+#[inline(always)]
+fn accumulate_512(acc: &mut [u64; ACC_NB], data: &[u8], secret: &[u8]) {
+    for i in 0..ACC_NB {
+        let data_val = read_u64(data, 8 * i);
+        let data_key = data_val ^ read_u64(secret, 8 * i);
+        acc[i ^ 1] = acc[i ^ 1].wrapping_add(data_val);
+        acc[i] = acc[i].wrapping_add((data_key & 0xFFFFFFFF).wrapping_mul(data_key >> 32));
+    }
+}
+
+#[inline(always)]
+fn scramble_acc(acc: &mut [u64; ACC_NB], secret: &[u8]) {
+    for (i, acc_n) in acc.iter_mut().enumerate() {
+        let key = read_u64(secret, 8 * i);
+        let mut acc64 = *acc_n;
+        acc64 ^= acc64 >> 47;
+        acc64 ^= key;
+        *acc_n = acc64.wrapping_mul(PRIME32_1);
+    }
+}
+
+#[inline(always)]
+fn merge_accs(acc: &[u64; ACC_NB], secret: &[u8], start: u64) -> u64 {
+    let mut result = start;
+    for i in 0..4 {
+        result = result.wrapping_add(mul128_fold64(
+            acc[2 * i] ^ read_u64(secret, 16 * i),
+            acc[2 * i + 1] ^ read_u64(secret, 16 * i + 8),
+        ));
+    }
+    avalanche(result)
+}
+
+// Derives a secret from the default secret and a seed.
+fn custom_secret(seed: u64) -> [u8; DEFAULT_SECRET_SIZE] {
+    let mut secret = [0; DEFAULT_SECRET_SIZE];
+    for i in 0..DEFAULT_SECRET_SIZE / 16 {
+        let lo = read_u64(&DEFAULT_SECRET, 16 * i).wrapping_add(seed);
+        let hi = read_u64(&DEFAULT_SECRET, 16 * i + 8).wrapping_sub(seed);
+        secret[16 * i..16 * i + 8].copy_from_slice(&lo.to_le_bytes());
+        secret[16 * i + 8..16 * i + 16].copy_from_slice(&hi.to_le_bytes());
+    }
+    secret
+}
+
+#[inline(always)]
+fn mix16(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let input_lo = read_u64(data, 0);
+    let input_hi = read_u64(data, 8);
+    mul128_fold64(
+        input_lo ^ read_u64(secret, 0).wrapping_add(seed),
+        input_hi ^ read_u64(secret, 8).wrapping_sub(seed),
+    )
+}
+
+#[inline(always)]
+fn mix32(acc: (u64, u64), data1: &[u8], data2: &[u8], secret: &[u8], seed: u64) -> (u64, u64) {
+    let mut low = acc.0.wrapping_add(mix16(data1, secret, seed));
+    low ^= read_u64(data2, 0).wrapping_add(read_u64(data2, 8));
+    let mut high = acc.1.wrapping_add(mix16(data2, &secret[16..], seed));
+    high ^= read_u64(data1, 0).wrapping_add(read_u64(data1, 8));
+    (low, high)
+}
+
+#[inline(always)]
+fn mul128_fold64(lhs: u64, rhs: u64) -> u64 {
+    let product = (lhs as u128).wrapping_mul(rhs as u128);
+    (product as u64) ^ ((product >> 64) as u64)
+}
+
+#[inline(always)]
+fn xxh64_avalanche(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(PRIME64_2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(PRIME64_3);
+    h ^= h >> 32;
+    h
+}
+
+#[inline(always)]
+fn avalanche(mut h: u64) -> u64 {
+    h ^= h >> 37;
+    h = h.wrapping_mul(PRIME_MX1);
+    h ^= h >> 32;
+    h
+}
+
+#[inline(always)]
+fn rrmxmx(mut h: u64, len: u64) -> u64 {
+    h ^= h.rotate_left(49) ^ h.rotate_left(24);
+    h = h.wrapping_mul(PRIME_MX2);
+    h ^= (h >> 35).wrapping_add(len);
+    h = h.wrapping_mul(PRIME_MX2);
+    h ^= h >> 28;
+    h
+}
+
+#[inline(always)]
+fn to_u128(low: u64, high: u64) -> u128 {
+    ((high as u128) << 64) | low as u128
+}
+
+#[inline(always)]
+fn read_u32(slice: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&slice[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+#[inline(always)]
+fn read_u64(slice: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&slice[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIME32: u64 = 2654435761;
+    const PRIME64: u64 = 11400714785074694797;
+
+    // Generates the same pseudo-random buffer as the reference sanity checks.
+    fn sanity_buffer(len: usize) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(len);
+        let mut byte_gen = PRIME32;
+        for _ in 0..len {
+            buffer.push((byte_gen >> 56) as u8);
+            byte_gen = byte_gen.wrapping_mul(PRIME64);
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_xxh3_64() {
+        let buffer = sanity_buffer(2367);
+        let vectors: [(usize, u64, u64); 13] = [
+            (0, 0x2D06800538D394C2, 0xA8A6B918B2F0364A),
+            (1, 0xC44BDFF4074EECDB, 0x032BE332DD766EF8),
+            (6, 0x27B56A84CD2D7325, 0x84589C116AB59AB9),
+            (12, 0xA713DAF0DFBB77E7, 0xE7303E1B2336DE0E),
+            (24, 0xA3FE70BF9D3510EB, 0x850E80FC35BDD690),
+            (48, 0x397DA259ECBA1F11, 0xADC2CBAA44ACC616),
+            (80, 0xBCDEFBBB2C47C90A, 0xC6DD0CB699532E73),
+            (195, 0xCD94217EE362EC3A, 0xBA68003D370CB3D9),
+            (403, 0xCDEB804D65C6DEA4, 0x6259F6ECFD6443FD),
+            (512, 0x617E49599013CB6B, 0x3CE457DE14C27708),
+            (2048, 0xDD59E2C3A5F038E0, 0x66F81670669ABABC),
+            (2240, 0x6E73A90539CF2948, 0x757BA8487D1B5247),
+            (2367, 0xCB37AEB9E5D361ED, 0xD2DB3415B942B42A),
+        ];
+        for (len, no_seed, with_seed) in vectors.iter() {
+            assert_eq!(*no_seed, xxh3_64_slice(&buffer[..*len], 0), "len {}", len);
+            assert_eq!(
+                *with_seed,
+                xxh3_64_slice(&buffer[..*len], PRIME64),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_xxh3_64_with_secret() {
+        let buffer = sanity_buffer(2367);
+        let secret = &buffer[7..7 + SECRET_SIZE_MIN + 11];
+        let vectors: [(usize, u64); 13] = [
+            (0, 0x3559D64878C5C66C),
+            (1, 0x8A52451418B2DA4D),
+            (6, 0x82C90AB0519369AD),
+            (12, 0x14631E773B78EC57),
+            (24, 0xCDD5542E4A9D9FE8),
+            (48, 0x33ABD54D094B2534),
+            (80, 0xE687BA1684965297),
+            (195, 0xA057273F5EECFB20),
+            (403, 0x14546019124D43B8),
+            (512, 0x7564693DD526E28D),
+            (2048, 0xD32E975821D6519F),
+            (2240, 0xB26C938C7AF3A71F),
+            (2367, 0x293FA8E5173BB5E7),
+        ];
+        for (len, expected) in vectors.iter() {
+            assert_eq!(
+                *expected,
+                xxh3_64_with_secret(&buffer[..*len], secret),
+                "len {}",
+                len
+            );
+        }
+
+        // The default secret with no seed is the unseeded hash.
+        assert_eq!(
+            xxh3_64_slice(&buffer, 0),
+            xxh3_64_with_secret(&buffer, &DEFAULT_SECRET)
+        );
+    }
+
+    #[test]
+    fn test_xxh3_64_with_secret_and_seed() {
+        let buffer = sanity_buffer(2367);
+        let secret = &buffer[7..7 + SECRET_SIZE_MIN + 11];
+        let vectors: [(usize, u64); 15] = [
+            (0, 0xA8A6B918B2F0364A),
+            (1, 0x032BE332DD766EF8),
+            (6, 0x84589C116AB59AB9),
+            (12, 0xE7303E1B2336DE0E),
+            (24, 0x850E80FC35BDD690),
+            (48, 0xADC2CBAA44ACC616),
+            (80, 0xC6DD0CB699532E73),
+            (195, 0xBA68003D370CB3D9),
+            (240, 0xCC0F58C27EF3D8EE),
+            (241, 0x454805371DF98A91),
+            (403, 0x14546019124D43B8),
+            (512, 0x7564693DD526E28D),
+            (2048, 0xD32E975821D6519F),
+            (2240, 0xB26C938C7AF3A71F),
+            (2367, 0x293FA8E5173BB5E7),
+        ];
+        for (len, expected) in vectors.iter() {
+            let data = &buffer[..*len];
+            assert_eq!(
+                *expected,
+                xxh3_64_with_secret_and_seed(data, secret, PRIME64),
+                "len {}",
+                len
+            );
+            if *len <= MIDSIZE_MAX {
+                assert_eq!(*expected, xxh3_64_slice(data, PRIME64));
+            } else {
+                assert_eq!(*expected, xxh3_64_with_secret(data, secret));
+            }
+        }
+
+        // With the secret derived from the seed, it is plain seeded hashing.
+        let secret = xxh3_generate_secret(PRIME64);
+        for len in [0, 100, 240, 241, 1000].iter() {
+            assert_eq!(
+                xxh3_64_slice(&buffer[..*len], PRIME64),
+                xxh3_64_with_secret_and_seed(&buffer[..*len], &secret, PRIME64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_xxh3_64_digest_with_secret() {
+        let buffer = sanity_buffer(2367);
+        let secrets = [
+            &buffer[7..7 + SECRET_SIZE_MIN],
+            &buffer[7..7 + SECRET_SIZE_MIN + 11],
+            &buffer[100..100 + SECRET_SIZE_MAX],
+            &xxh3_generate_secret(PRIME64)[..],
+        ];
+        for secret in secrets.iter() {
+            for len in [0, 1, 6, 12, 24, 48, 80, 195, 240, 241, 403, 512, 2048, 2367].iter() {
+                let mut digest = Xxh3_64::with_secret(secret);
+                for byte in &buffer[..*len] {
+                    digest.write(&[*byte]);
+                }
+                assert_eq!(
+                    xxh3_64_with_secret(&buffer[..*len], secret),
+                    digest.finish(),
+                    "secret {} bytes, len {}",
+                    secret.len(),
+                    len
+                );
+            }
+        }
+
+        let mut digest = Xxh3_64::with_secret(&buffer[7..7 + SECRET_SIZE_MIN + 11]);
+        digest.write(&buffer);
+        assert_eq!(0x293FA8E5173BB5E7, digest.finish());
+    }
+
+    #[test]
+    #[should_panic(expected = "secret must be 136 to 256 bytes, got 135")]
+    fn test_xxh3_64_digest_short_secret() {
+        Xxh3_64::with_secret(&[0; SECRET_SIZE_MIN - 1]);
+    }
+
+    #[test]
+    fn test_xxh3_64_digest() {
+        fn digest_slice(bytes: &[u8], seed: u64) -> u64 {
+            let mut digest = Xxh3_64::with_seed(seed);
+            for i in bytes {
+                digest.write(&[*i]);
+            }
+            digest.finish()
+        }
+
+        let buffer = sanity_buffer(2367);
+        for len in 0..=buffer.len() {
+            for &seed in &[0, PRIME64] {
+                assert_eq!(
+                    xxh3_64_slice(&buffer[..len], seed),
+                    digest_slice(&buffer[..len], seed),
+                    "len {} seed {}",
+                    len,
+                    seed
+                );
+            }
+        }
+
+        // Writes larger than the internal buffer take the direct path.
+        for &chunk in &[63, 64, 255, 256, 257, 1000] {
+            let mut digest = Xxh3_64::with_seed(PRIME64);
+            for c in buffer.chunks(chunk) {
+                digest.write(c);
+            }
+            assert_eq!(xxh3_64_slice(&buffer, PRIME64), digest.finish());
+        }
+    }
+
+    #[test]
+    fn test_xxh3_generate_secret() {
+        assert_eq!(DEFAULT_SECRET, xxh3_generate_secret(0));
+
+        let buffer = sanity_buffer(2367);
+        let secret = xxh3_generate_secret(PRIME64);
+        for len in (MIDSIZE_MAX + 1..=buffer.len()).step_by(7) {
+            assert_eq!(
+                xxh3_64_slice(&buffer[..len], PRIME64),
+                xxh3_64_with_secret(&buffer[..len], &secret),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "secret must be at least 136 bytes")]
+    fn test_xxh3_64_with_short_secret() {
+        xxh3_64_with_secret(b"hello", &[0; SECRET_SIZE_MIN - 1]);
+    }
+
+    #[test]
+    fn test_xxh3_128() {
+        let buffer = sanity_buffer(1024);
+        let vectors: [(usize, u128, u128); 5] = [
+            (
+                0,
+                0x99AA06D3014798D86001C324468D497F,
+                0x00FEAA732A3CE25EA986DFC5D7605BFE,
+            ),
+            (
+                7,
+                0xDD9B6039F79EC416081C22DD284A2F0A,
+                0x833CBA0282DD6619D5DA197923938E53,
+            ),
+            (
+                63,
+                0x7FC0AE04A6D2BB0B5E4CB79E9A8121D9,
+                0xAEC92695D77FEA89C6EE481741654B12,
+            ),
+            (
+                240,
+                0xAA4202DAA2769DC85C9AAE94C8EBE5A0,
+                0x29D2133D6EA58C5B604E98DB085C1864,
+            ),
+            (
+                1024,
+                0x0D30D24071C64C57DD85C9B5C1109C5C,
+                0x17600EFE2B493A18EF368A8A2EBABAEF,
+            ),
+        ];
+        for (len, no_seed, with_seed) in vectors.iter() {
+            assert_eq!(*no_seed, xxh3_128_slice(&buffer[..*len], 0), "len {}", len);
+            assert_eq!(
+                *with_seed,
+                xxh3_128_slice(&buffer[..*len], PRIME64),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_xxh128_digest() {
+        fn digest_slice(bytes: &[u8], seed: u64) -> u128 {
+            let mut digest = Xxh128::with_seed(seed);
+            for i in bytes {
+                digest.write(&[*i]);
+            }
+            digest.finish()
+        }
+
+        let buffer = sanity_buffer(2367);
+        for len in 0..=buffer.len() {
+            for &seed in &[0, PRIME64] {
+                assert_eq!(
+                    xxh3_128_slice(&buffer[..len], seed),
+                    digest_slice(&buffer[..len], seed),
+                    "len {} seed {}",
+                    len,
+                    seed
+                );
+            }
+        }
+
+        for &chunk in &[63, 64, 255, 256, 257, 1000] {
+            let mut digest = Xxh128::with_seed(PRIME64);
+            for c in buffer.chunks(chunk) {
+                digest.write(c);
+            }
+            assert_eq!(xxh3_128_slice(&buffer, PRIME64), digest.finish());
+            assert_eq!(
+                xxh3_128_slice(&buffer, PRIME64) as u64,
+                Hasher::finish(&digest)
+            );
+        }
+    }
+
+    #[test]
+    fn test_xxh3_64_and_128() {
+        let buffer = sanity_buffer(2367);
+        for len in 0..=buffer.len() {
+            for &seed in &[0, PRIME64] {
+                let input = &buffer[..len];
+                assert_eq!(
+                    (xxh3_64_slice(input, seed), xxh3_128_slice(input, seed)),
+                    xxh3_64_and_128(input, seed),
+                    "len {}",
+                    len
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_xxh3_128_hex() {
+        let buffer = sanity_buffer(1024);
+        let mut digest = Xxh128::with_seed(PRIME64);
+        digest.write(&buffer);
+        assert_eq!(
+            format!("{:032x}", xxh3_128_slice(&buffer, PRIME64)),
+            digest.finish_hex()
+        );
+        assert_eq!(digest.finish_hex(), xxh3_128_hex(&buffer, PRIME64));
+        assert_eq!("99aa06d3014798d86001c324468d497f", xxh3_128_hex(b"", 0));
+
+        let key = (0..10000u32)
+            .map(u32::to_le_bytes)
+            .find(|key| xxh3_128_slice(key, 0) >> 120 == 0)
+            .unwrap();
+        let hex = xxh3_128_hex(&key, 0);
+        assert_eq!(32, hex.len());
+        assert!(hex.starts_with("00"), "{}", hex);
+    }
+
+    #[test]
+    fn test_xxh3_256() {
+        // Pinned, since no other implementation can check it.
+        let mut expected = [0; 32];
+        expected[..16].copy_from_slice(&0x99AA06D3014798D86001C324468D497Fu128.to_be_bytes());
+        expected[16..].copy_from_slice(&0x77F64DAA6C5776E21FFC9A053350E6F8u128.to_be_bytes());
+        assert_eq!(expected, xxh3_256(b"", 0));
+
+        let buffer = sanity_buffer(2367);
+        for &len in &[0, 1, 16, 200, 240, 241, 1024, 2367] {
+            for &seed in &[0, PRIME64] {
+                let data = &buffer[..len];
+                let hash = xxh3_256(data, seed);
+                assert_eq!(
+                    xxh128_canonical(xxh3_128_slice(data, seed)),
+                    hash[..16],
+                    "len {}",
+                    len
+                );
+                let tweaked = [data, XXH3_256_TWEAK].concat();
+                assert_eq!(
+                    xxh128_canonical(xxh3_128_slice(&tweaked, seed)),
+                    hash[16..],
+                    "len {}",
+                    len
+                );
+                assert_ne!(hash[..16], hash[16..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_xxh128_canonical() {
+        let hash = xxh3_128_slice(b"", 0);
+        assert_eq!(
+            [
+                0x99, 0xaa, 0x06, 0xd3, 0x01, 0x47, 0x98, 0xd8, 0x60, 0x01, 0xc3, 0x24, 0x46, 0x8d,
+                0x49, 0x7f
+            ],
+            xxh128_canonical(hash)
+        );
+        assert_eq!(hash, xxh128_from_canonical(&xxh128_canonical(hash)));
+
+        let hash = xxh3_128_slice(&sanity_buffer(1024), PRIME64);
+        assert_eq!(hash, xxh128_from_canonical(&xxh128_canonical(hash)));
+    }
+
+    #[test]
+    fn test_xxh128_cmp() {
+        // A larger high word wins even if the low word is smaller.
+        assert_eq!(
+            Ordering::Greater,
+            xxh128_cmp(to_u128(0, 2), to_u128(u64::MAX, 1))
+        );
+        assert_eq!(Ordering::Less, xxh128_cmp(to_u128(1, 7), to_u128(2, 7)));
+        assert_eq!(Ordering::Equal, xxh128_cmp(to_u128(3, 4), to_u128(3, 4)));
+
+        let buffer = sanity_buffer(1024);
+        let a = xxh3_128_slice(&buffer, 0);
+        let b = xxh3_128_slice(&buffer, PRIME64);
+        assert_eq!(Ordering::Greater, xxh128_cmp(b, a));
+        assert_eq!(a.cmp(&b), xxh128_cmp(a, b));
+    }
+}
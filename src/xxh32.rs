@@ -0,0 +1,350 @@
+use core::hash::BuildHasher;
+use core::hash::Hasher;
+
+use crate::XxHasher;
+
+const PRIME32_1: u32 = 0x9E3779B1;
+const PRIME32_2: u32 = 0x85EBCA77;
+const PRIME32_3: u32 = 0xC2B2AE3D;
+const PRIME32_4: u32 = 0x27D4EB2F;
+const PRIME32_5: u32 = 0x165667B1;
+
+const STRIPE_LEN_16: usize = 16;
+
+pub fn xxh32_str(s: &str, seed: u32) -> u32 {
+    xxh32_slice(s.as_bytes(), seed)
+}
+
+pub fn xxh32_slice(mut slice: &[u8], seed: u32) -> u32 {
+    let acc: u32;
+
+    let input_len = slice.len() as u64;
+
+    if slice.len() < STRIPE_LEN_16 {
+        // Special case: input is less than 16 bytes.
+        // The algorithm then proceeds directly to step 4.
+        acc = seed.wrapping_add(PRIME32_5);
+    } else {
+        // Step 1. Initialise internal accumulators
+        let mut accs = (
+            seed.wrapping_add(PRIME32_1).wrapping_add(PRIME32_2),
+            seed.wrapping_add(PRIME32_2),
+            seed,
+            seed.wrapping_sub(PRIME32_1),
+        );
+        // Step 2. Process stripes
+        let mut stripes = slice.chunks_exact(STRIPE_LEN_16);
+        for stripe in &mut stripes {
+            accs = Xxh32::process_stripe(accs, stripe);
+        }
+        slice = stripes.remainder();
+        // Step 3. Accumulator convergence
+        acc = converge(accs);
+    }
+    finalize(acc, input_len, slice)
+}
+
+pub fn xxh32_canonical(hash: u32) -> [u8; 4] {
+    // The canonical representation is big-endian, matching `XXH32_canonicalFromHash`.
+    hash.to_be_bytes()
+}
+
+pub fn xxh32_from_canonical(bytes: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*bytes)
+}
+
+// xxh32_hex returns `xxh32_slice(bytes, seed)` as 8 lowercase hex digits, keeping
+// leading zeros.
+#[cfg(feature = "std")]
+pub fn xxh32_hex(bytes: &[u8], seed: u32) -> String {
+    format!("{:08x}", xxh32_slice(bytes, seed))
+}
+
+#[repr(align(4))]
+struct Align32<T>(T);
+
+// Xxh32 represents the xxHash digest algorithm(32-bits).
+pub struct Xxh32 {
+    seed: u32,
+    acc1: u32,
+    acc2: u32,
+    acc3: u32,
+    acc4: u32,
+    buffer: Align32<[u8; STRIPE_LEN_16]>,
+    buffer_len: usize,
+    // u64 rather than usize, which would overflow after 4 GiB of input on 32-bit targets.
+    input_len: u64,
+}
+
+impl Xxh32 {
+    pub fn with_seed(seed: u32) -> Xxh32 {
+        Xxh32 {
+            seed,
+            acc1: seed.wrapping_add(PRIME32_1).wrapping_add(PRIME32_2),
+            acc2: seed.wrapping_add(PRIME32_2),
+            acc3: seed,
+            acc4: seed.wrapping_sub(PRIME32_1),
+            buffer: Align32([0; STRIPE_LEN_16]),
+            buffer_len: 0,
+            input_len: 0,
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.input_len = self.input_len.wrapping_add(bytes.len() as u64);
+
+        if bytes.len() + self.buffer_len < STRIPE_LEN_16 {
+            self.buffer.0[self.buffer_len..bytes.len() + self.buffer_len].copy_from_slice(bytes);
+            self.buffer_len += bytes.len();
+        } else {
+            // Need to consume extra bytes.
+            let mut accs = (self.acc1, self.acc2, self.acc3, self.acc4);
+            let (head, rest) = bytes.split_at(STRIPE_LEN_16 - self.buffer_len);
+            self.buffer.0[self.buffer_len..].copy_from_slice(head);
+            accs = Xxh32::process_stripe(accs, &self.buffer.0);
+            let mut stripes = rest.chunks_exact(STRIPE_LEN_16);
+            for stripe in &mut stripes {
+                accs = Xxh32::process_stripe(accs, stripe);
+            }
+            let remainder = stripes.remainder();
+            self.acc1 = accs.0;
+            self.acc2 = accs.1;
+            self.acc3 = accs.2;
+            self.acc4 = accs.3;
+            self.buffer_len = remainder.len();
+            self.buffer.0[..remainder.len()].copy_from_slice(remainder);
+        }
+    }
+
+    // finish_hex returns `finish` as 8 lowercase hex digits, keeping leading zeros.
+    #[cfg(feature = "std")]
+    pub fn finish_hex(&self) -> String {
+        format!("{:08x}", self.finish())
+    }
+
+    pub fn finish(&self) -> u32 {
+        let acc = if self.input_len >= STRIPE_LEN_16 as u64 {
+            converge((self.acc1, self.acc2, self.acc3, self.acc4))
+        } else {
+            // Special case: input is less than 16 bytes.
+            // The algorithm then proceeds directly to step 4.
+            self.seed.wrapping_add(PRIME32_5)
+        };
+        finalize(acc, self.input_len, &self.buffer.0[..self.buffer_len])
+    }
+
+    // Xxh32 used to implement `BuildHasher` itself, always building an unseeded digest
+    // whatever its own seed. Like `Xxh64::build_hasher`, this only keeps direct calls
+    // compiling; `HashMap`s must be built with `Xxh32BuildHasher`.
+    #[deprecated(since = "0.2.0", note = "use `Xxh32BuildHasher` to build hashers")]
+    pub fn build_hasher(&self) -> Xxh32 {
+        Xxh32::with_seed(0)
+    }
+
+    #[inline(always)]
+    fn process_stripe(mut accs: (u32, u32, u32, u32), stripe: &[u8]) -> (u32, u32, u32, u32) {
+        // Step 2. Process stripes
+        // Each lane read its associated 32-bit value using little-endian convention.
+        accs.0 = round(accs.0, read_lane(stripe, 0));
+        accs.1 = round(accs.1, read_lane(stripe, 1));
+        accs.2 = round(accs.2, read_lane(stripe, 2));
+        accs.3 = round(accs.3, read_lane(stripe, 3));
+        accs
+    }
+}
+
+impl Hasher for Xxh32 {
+    fn finish(&self) -> u64 {
+        self.finish() as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+}
+
+// Xxh32BuildHasher builds `Xxh32` digests with a configured seed, e.g. for `HashMap`.
+#[derive(Clone, Copy, Default)]
+pub struct Xxh32BuildHasher {
+    seed: u32,
+}
+
+impl Xxh32BuildHasher {
+    pub fn with_seed(seed: u32) -> Xxh32BuildHasher {
+        Xxh32BuildHasher { seed }
+    }
+}
+
+impl BuildHasher for Xxh32BuildHasher {
+    type Hasher = Xxh32;
+
+    #[inline(always)]
+    fn build_hasher(&self) -> Self::Hasher {
+        Xxh32::with_seed(self.seed)
+    }
+}
+
+// XXH32 takes a 32-bit seed, so only the low 32 bits of `seed` are used.
+impl XxHasher for Xxh32 {
+    type Output = u32;
+
+    fn with_seed(seed: u64) -> Xxh32 {
+        Xxh32::with_seed(seed as u32)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.write(bytes);
+    }
+
+    fn digest(&self) -> u32 {
+        self.finish()
+    }
+}
+
+impl Default for Xxh32 {
+    fn default() -> Self {
+        Xxh32::with_seed(0)
+    }
+}
+
+// Reads the n-th 32-bit lane of a stripe.
+#[inline(always)]
+fn read_lane(stripe: &[u8], n: usize) -> u32 {
+    let mut lane = [0u8; 4];
+    lane.copy_from_slice(&stripe[n * 4..n * 4 + 4]);
+    u32::from_le_bytes(lane)
+}
+
+// Step 3, shared by the one-shot and streaming paths.
+#[inline(always)]
+fn converge(accs: (u32, u32, u32, u32)) -> u32 {
+    accs.0
+        .rotate_left(1)
+        .wrapping_add(accs.1.rotate_left(7))
+        .wrapping_add(accs.2.rotate_left(12))
+        .wrapping_add(accs.3.rotate_left(18))
+}
+
+// Steps 4 to 6, shared by the one-shot and streaming paths.
+// `tail` is the input left over after the last complete stripe.
+#[inline(always)]
+fn finalize(mut acc: u32, input_len: u64, tail: &[u8]) -> u32 {
+    // Step 4. Add input length
+    // Only the lower 32 bits of the length are used.
+    acc = acc.wrapping_add(input_len as u32);
+    // Step 5. Consume remaining input
+    let mut lanes = tail.chunks_exact(4);
+    for lane in &mut lanes {
+        acc = acc.wrapping_add(read_lane(lane, 0).wrapping_mul(PRIME32_3));
+        acc = acc.rotate_left(17).wrapping_mul(PRIME32_4);
+    }
+    for &byte in lanes.remainder() {
+        acc = acc.wrapping_add((byte as u32).wrapping_mul(PRIME32_5));
+        acc = acc.rotate_left(11).wrapping_mul(PRIME32_1);
+    }
+    // Step 6. Final mix (avalanche)
+    acc ^= acc >> 15;
+    acc = acc.wrapping_mul(PRIME32_2);
+    acc ^= acc >> 13;
+    acc = acc.wrapping_mul(PRIME32_3);
+    acc ^= acc >> 16;
+    acc
+}
+
+#[inline(always)]
+fn round(mut acc_n: u32, lane_n: u32) -> u32 {
+    acc_n = acc_n.wrapping_add(lane_n.wrapping_mul(PRIME32_2));
+    acc_n = acc_n.rotate_left(13);
+    acc_n = acc_n.wrapping_mul(PRIME32_1);
+    acc_n
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_xxh32_hex() {
+        let mut digest = Xxh32::with_seed(10);
+        digest.write(b"hello");
+        assert_eq!(
+            format!("{:08x}", xxh32_slice(b"hello", 10)),
+            digest.finish_hex()
+        );
+        assert_eq!(digest.finish_hex(), xxh32_hex(b"hello", 10));
+
+        let key = (0..10000u32)
+            .map(u32::to_le_bytes)
+            .find(|key| xxh32_slice(key, 0) >> 24 == 0)
+            .unwrap();
+        let hex = xxh32_hex(&key, 0);
+        assert_eq!(8, hex.len());
+        assert!(hex.starts_with("00"), "{}", hex);
+    }
+
+    #[test]
+    fn test_xxh32() {
+        assert_eq!(46947589, xxh32_slice(b"", 0));
+        assert_eq!(3068971186, xxh32_slice(b"1", 0));
+        assert_eq!(2325985052, xxh32_slice(b"01234", 0));
+        assert_eq!(1904468236, xxh32_slice(b"01234567890123456789", 0));
+
+        let buffer = b"0123456789".repeat(10);
+        assert_eq!(100, buffer.len());
+        assert_eq!(4042796470, xxh32_slice(&buffer, 0));
+        assert_eq!(3947588659, xxh32_slice(&buffer, 10));
+
+        assert_eq!(xxh32_slice(b"01234", 0), xxh32_str("01234", 0));
+        assert_eq!(
+            xxh32_slice(b"hello", u32::MAX),
+            xxh32_str("hello", u32::MAX)
+        );
+
+        let s = Xxh32BuildHasher::default();
+        let mut map = HashMap::with_capacity_and_hasher(10, s);
+        map.insert("qwer", 1);
+    }
+
+    #[test]
+    fn test_xxh32_build_hasher() {
+        // The builder's seed reaches the digests it builds.
+        for &seed in &[0, 10, u32::MAX] {
+            let mut hasher = Xxh32BuildHasher::with_seed(seed).build_hasher();
+            hasher.write(b"hello");
+            assert_eq!(xxh32_slice(b"hello", seed), hasher.finish());
+        }
+    }
+
+    #[test]
+    fn test_xxh32_canonical() {
+        let hash = xxh32_slice(b"", 0);
+        assert_eq!([0x02, 0xcc, 0x5d, 0x05], xxh32_canonical(hash));
+        assert_eq!(hash, xxh32_from_canonical(&xxh32_canonical(hash)));
+
+        let hash = xxh32_slice(b"0123456789", 0);
+        assert_eq!(hash, xxh32_from_canonical(&xxh32_canonical(hash)));
+    }
+
+    #[test]
+    fn test_xxh32_digest() {
+        fn digest_slice(bytes: &[u8]) -> u32 {
+            let mut digest = Xxh32::with_seed(10);
+            for i in bytes {
+                digest.write(&[*i]);
+            }
+            digest.finish()
+        }
+
+        let mut test_bytes = vec![];
+        for i in 0..1000 {
+            test_bytes.push(i as u8);
+            assert_eq!(
+                xxh32_slice(test_bytes.as_ref(), 10),
+                digest_slice(test_bytes.as_ref())
+            )
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_xxh64"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn run_with_stdin(args: &[&str], input: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xxh64"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// Returns a fresh scratch directory for the named test.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xxh-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_stdin() {
+    let expected = format!("{:016x}  -\n", xxh::xxh64_slice(b"hello\n", 0));
+    assert_eq!(expected, run_with_stdin(&["-"], b"hello\n"));
+    assert_eq!(expected, run_with_stdin(&[], b"hello\n"));
+}
+
+#[test]
+fn test_check() {
+    let dir = scratch_dir("check");
+    let file = dir.join("data.txt");
+    let missing = dir.join("missing.txt");
+    let list = dir.join("sums.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let file = file.to_str().unwrap();
+    let list = list.to_str().unwrap();
+
+    let output = run(&[file]);
+    assert!(output.status.success());
+    fs::write(list, &output.stdout).unwrap();
+    let output = run(&["--check", list]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{}: OK\n", file),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    fs::write(file, b"hello!\n").unwrap();
+    let output = run(&["--check", list]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!("{}: FAILED\n", file),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    fs::write(list, format!("0000000000000000  {}\n", missing.display())).unwrap();
+    let output = run(&["--check", list]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!("{}: FAILED open or read\n", missing.display()),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_errors() {
+    let dir = scratch_dir("errors");
+    let missing = dir.join("missing.txt");
+    let missing = missing.to_str().unwrap();
+
+    let output = run(&[missing]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!(
+            "xxh64: cannot open '{}': No such file or directory\n",
+            missing
+        ),
+        String::from_utf8(output.stderr).unwrap()
+    );
+
+    // The remaining files are still hashed.
+    let file = dir.join("data.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let file = file.to_str().unwrap();
+    let output = run(&[missing, file]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        format!("{:016x}  {}\n", xxh::xxh64_slice(b"hello\n", 0), file),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let output = run(&["--check", missing]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with(&format!("xxh64: cannot read '{}'", missing)));
+
+    for args in &[
+        &["--seed"][..],
+        &["--check", "a", "--check", "b"],
+        &["--check", "a", file],
+    ] {
+        let output = run(args);
+        assert_eq!(Some(2), output.status.code(), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("xxh64: "), "{}", stderr);
+        assert!(stderr.contains("usage: xxh64"), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_progress() {
+    // stderr is not a terminal here, so --progress must not change any output.
+    let dir = scratch_dir("progress");
+    let file = dir.join("data.bin");
+    fs::write(&file, vec![7u8; 1 << 20]).unwrap();
+    let file = file.to_str().unwrap();
+
+    let output = run(&["--progress", file]);
+    assert!(output.status.success());
+    assert_eq!(run(&[file]).stdout, output.stdout);
+    assert!(output.stderr.is_empty());
+    assert_eq!(
+        run_with_stdin(&["-"], b"hello\n"),
+        run_with_stdin(&["--progress", "-"], b"hello\n")
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_buffer_size() {
+    let expected = format!("{:016x}  -\n", xxh::xxh64_slice(b"hello\n", 0));
+    for size in &["1", "4096", "512K", "512k", "1M", "1g"] {
+        assert_eq!(
+            expected,
+            run_with_stdin(&["--buffer-size", size, "-"], b"hello\n")
+        );
+    }
+
+    for size in &[
+        "",
+        "0",
+        "0K",
+        "K",
+        "12Q",
+        "-1",
+        "1.5M",
+        "99999999999999999999",
+    ] {
+        let output = run(&["--buffer-size", size, "-"]);
+        assert_eq!(Some(2), output.status.code());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("invalid buffer size"));
+    }
+    assert_eq!(Some(2), run(&["--buffer-size"]).status.code());
+}
+
+#[test]
+fn test_json() {
+    let dir = scratch_dir("json");
+    let file = dir.join("a\"b\\c.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let file = file.to_str().unwrap();
+    let hash = xxh::xxh64_slice(b"hello\n", 0);
+    let entry = format!(
+        "{{\"file\": \"{}\", \"algo\": \"xxh64\", \"seed\": 0, \"dec\": {}, \"hex\": \"{:016x}\", \"seconds\": ",
+        file.replace('\\', "\\\\").replace('"', "\\\""),
+        hash,
+        hash
+    );
+
+    let output = run(&["--json", file]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(&entry), "{}", stdout);
+    assert!(stdout.ends_with("}\n"));
+
+    let output = run(&["--json", file, file]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(&format!("[{}", entry)), "{}", stdout);
+    assert_eq!(2, stdout.matches(&entry).count());
+    assert!(stdout.ends_with("}]\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_seed() {
+    let dir = scratch_dir("seed");
+    let file = dir.join("data.txt");
+    let list = dir.join("sums.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let file = file.to_str().unwrap();
+    let list = list.to_str().unwrap();
+
+    let expected = format!(
+        "# seed 0xdeadbeef\n{:016x}  {}\n",
+        xxh::xxh64_slice(b"hello\n", 0xdeadbeef),
+        file
+    );
+    for seed in &["0xdeadbeef", "0XDEADBEEF", "3735928559"] {
+        let output = run(&["--seed", seed, file]);
+        assert!(output.status.success());
+        assert_eq!(expected, String::from_utf8(output.stdout).unwrap());
+    }
+    // The default seed keeps the plain output.
+    let output = run(&["--seed", "0", file]);
+    assert_eq!(
+        format!("{:016x}  {}\n", xxh::xxh64_slice(b"hello\n", 0), file),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    // The recorded seed is honoured when checking.
+    fs::write(list, &expected).unwrap();
+    let output = run(&["--check", list]);
+    assert!(output.status.success());
+    assert_eq!(
+        format!("{}: OK\n", file),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    for seed in &["", "0x", "-1", "+1", "0xfffffffffffffffff", "deadbeef"] {
+        let output = run(&["--seed", seed, file]);
+        assert_eq!(Some(2), output.status.code());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("invalid seed"));
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_algo() {
+    let dir = scratch_dir("algo");
+    let file = dir.join("data.bin");
+    let list = dir.join("sums.txt");
+    let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + 3) as u8).collect();
+    fs::write(&file, &data).unwrap();
+    let file = file.to_str().unwrap();
+    let list = list.to_str().unwrap();
+
+    // Reference digests from the C implementation.
+    let vectors = [
+        ("xxh32", "c85a5152", "f8ce5633"),
+        ("xxh64", "5f235fa033f1a3fb", "d914a697ac0a257d"),
+        ("xxh3-64", "6c4f14bd97bd9e82", "a9c39e0dfbcee7fa"),
+        (
+            "xxh3-128",
+            "6bcc7eff62da44c26c4f14bd97bd9e82",
+            "b2c1febcf54acd2fa9c39e0dfbcee7fa",
+        ),
+    ];
+    for (algo, no_seed, with_seed) in vectors.iter() {
+        let header = if *algo == "xxh64" {
+            String::new()
+        } else {
+            format!("# algo {}\n", algo)
+        };
+        // A small buffer splits the input across many writes.
+        let output = run(&["--algo", algo, "--buffer-size", "100", file]);
+        assert!(output.status.success());
+        assert_eq!(
+            format!("{}{}  {}\n", header, no_seed, file),
+            String::from_utf8(output.stdout).unwrap()
+        );
+
+        let output = run(&["--algo", algo, "--seed", "10", file]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            format!("{}# seed 0xa\n{}  {}\n", header, with_seed, file),
+            stdout
+        );
+
+        fs::write(list, stdout).unwrap();
+        let output = run(&["--check", list]);
+        assert!(output.status.success());
+    }
+    assert_eq!(
+        format!("5f235fa033f1a3fb  {}\n", file),
+        String::from_utf8(run(&[file]).stdout).unwrap()
+    );
+
+    let output = run(&["--algo", "md5", file]);
+    assert_eq!(Some(2), output.status.code());
+    let output = run(&["--algo", "xxh32", "--seed", "0x100000000", file]);
+    assert_eq!(Some(2), output.status.code());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recursive() {
+    use std::os::unix::fs::symlink;
+
+    let dir = scratch_dir("recursive");
+    fs::create_dir_all(dir.join("sub/deeper")).unwrap();
+    fs::write(dir.join("b.txt"), b"b").unwrap();
+    fs::write(dir.join("sub/a.txt"), b"a").unwrap();
+    fs::write(dir.join("sub/deeper/c.txt"), b"c").unwrap();
+    fs::write(dir.join("z.txt"), b"z").unwrap();
+    symlink(dir.join("sub/a.txt"), dir.join("link.txt")).unwrap();
+    symlink(dir.join("sub"), dir.join("linked-dir")).unwrap();
+    // A loop back to the root, which must not be walked forever.
+    symlink(&dir, dir.join("sub/loop")).unwrap();
+    let root = dir.to_str().unwrap();
+
+    let line = |content: &[u8], path: &str| {
+        format!("{:016x}  {}/{}\n", xxh::xxh64_slice(content, 0), root, path)
+    };
+    let output = run(&["--recursive", root]);
+    assert!(output.status.success());
+    assert_eq!(
+        [
+            line(b"b", "b.txt"),
+            line(b"a", "sub/a.txt"),
+            line(b"c", "sub/deeper/c.txt"),
+            line(b"z", "z.txt"),
+        ]
+        .concat(),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    // Followed links are included, and each directory is only walked once.
+    let output = run(&["--recursive", "--follow-symlinks", root]);
+    assert!(output.status.success());
+    assert_eq!(
+        [
+            line(b"b", "b.txt"),
+            line(b"a", "link.txt"),
+            line(b"a", "linked-dir/a.txt"),
+            line(b"c", "linked-dir/deeper/c.txt"),
+            line(b"z", "z.txt"),
+        ]
+        .concat(),
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    // A dangling link is reported without aborting the walk.
+    symlink(dir.join("missing"), dir.join("dangling")).unwrap();
+    let output = run(&["--recursive", "--follow-symlinks", root]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("dangling"));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains(&line(b"z", "z.txt")));
+
+    fs::remove_dir_all(dir).unwrap();
+}